@@ -0,0 +1,223 @@
+use serde::{de::IntoDeserializer, Deserialize};
+use std::{env, path::PathBuf, process, thread, time::Duration};
+
+use crate::{
+    config::OutputFormat,
+    interface::{Filter, SortOrder},
+    report::FunctionMetric,
+};
+
+pub struct Args {
+    pub path: String,
+    pub include_vendor: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub extensions: Option<Vec<String>>,
+    pub threads: usize,
+    pub cache: Option<PathBuf>,
+    pub no_cache: bool,
+    pub clear_cache: bool,
+    pub watch: bool,
+    pub interval: Duration,
+    pub config: Option<PathBuf>,
+    pub check: bool,
+    pub format: Option<OutputFormat>,
+    pub baseline: Option<PathBuf>,
+    pub update_baseline: bool,
+    pub prune_baseline: bool,
+    pub save_scan: Option<PathBuf>,
+    pub diff: Option<String>,
+    pub changed_since: Option<String>,
+    pub trend: Option<String>,
+    pub output: Option<PathBuf>,
+    pub report: bool,
+    pub functions: bool,
+    pub functions_by: FunctionMetric,
+    pub no_tui: bool,
+    pub filter: Option<Filter>,
+    pub sort: Option<SortOrder>,
+    pub show: Option<String>,
+}
+
+impl Args {
+    pub fn parse() -> Self {
+        let mut path = None;
+        let mut include_vendor = false;
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        let mut extensions = None;
+        let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let mut cache = None;
+        let mut no_cache = false;
+        let mut clear_cache = false;
+        let mut watch = false;
+        let mut interval = Duration::from_secs(2);
+        let mut config = None;
+        let mut check = false;
+        let mut format = None;
+        let mut baseline = None;
+        let mut update_baseline = false;
+        let mut prune_baseline = false;
+        let mut save_scan = None;
+        let mut diff = None;
+        let mut changed_since = None;
+        let mut trend = None;
+        let mut output = None;
+        let mut report = false;
+        let mut functions = false;
+        let mut functions_by = FunctionMetric::default();
+        let mut no_tui = false;
+        let mut filter = None;
+        let mut sort = None;
+        let mut show = None;
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--include-vendor" => include_vendor = true,
+                "--include" => include.push(parse_value(&arg, args.next())),
+                "--exclude" => exclude.push(parse_value(&arg, args.next())),
+                "--extensions" => {
+                    let value = parse_value(&arg, args.next());
+                    let list = value
+                        .split(',')
+                        .map(|extension| extension.trim().trim_start_matches('.').to_string())
+                        .filter(|extension| !extension.is_empty())
+                        .collect();
+                    extensions = Some(list)
+                }
+                "--threads" => threads = parse_number(&arg, args.next()),
+                "--cache" => cache = Some(PathBuf::from(parse_value(&arg, args.next()))),
+                "--no-cache" => no_cache = true,
+                "--clear-cache" => clear_cache = true,
+                "--watch" => watch = true,
+                "--interval" => {
+                    interval = Duration::from_secs(parse_number(&arg, args.next()) as u64)
+                }
+                "--config" => config = Some(PathBuf::from(parse_value(&arg, args.next()))),
+                "--check" => check = true,
+                "--format" => format = Some(parse_enum(&arg, args.next())),
+                "--baseline" => baseline = Some(PathBuf::from(parse_value(&arg, args.next()))),
+                "--update-baseline" => update_baseline = true,
+                "--prune-baseline" => prune_baseline = true,
+                "--save-scan" => save_scan = Some(PathBuf::from(parse_value(&arg, args.next()))),
+                "--diff" => diff = Some(parse_value(&arg, args.next())),
+                "--changed-since" => changed_since = Some(parse_value(&arg, args.next())),
+                "--trend" => trend = Some(parse_value(&arg, args.next())),
+                "--output" => output = Some(PathBuf::from(parse_value(&arg, args.next()))),
+                "--report" => report = true,
+                "--show" => show = Some(parse_value(&arg, args.next())),
+                "--functions" => functions = true,
+                "--functions-by" => {
+                    functions = true;
+                    functions_by = parse_enum(&arg, args.next())
+                }
+                "--no-tui" => no_tui = true,
+                "--filter" => {
+                    let value = parse_value(&arg, args.next());
+                    let parsed = Filter::parse(&value).unwrap_or_else(|err| {
+                        eprintln!("ERROR: Invalid value for {arg}, {err}");
+                        process::exit(1);
+                    });
+                    filter = Some(parsed)
+                }
+                "--sort" => {
+                    let value = parse_value(&arg, args.next());
+                    let parsed = SortOrder::parse(&value).unwrap_or_else(|err| {
+                        eprintln!("ERROR: Invalid value for {arg}, {err}");
+                        process::exit(1);
+                    });
+                    sort = Some(parsed)
+                }
+                _ if arg.starts_with("--") => {
+                    eprintln!("ERROR: Unknown option {arg}");
+                    process::exit(1);
+                }
+                _ => path = Some(arg),
+            }
+        }
+
+        let path = path.unwrap_or_else(|| {
+            eprintln!("ERROR: Please input file path to scan");
+            process::exit(1);
+        });
+        if watch && changed_since.is_some() {
+            eprintln!("ERROR: --changed-since can't be combined with --watch");
+            process::exit(1);
+        }
+        if watch && filter.is_some() {
+            eprintln!("ERROR: --filter can't be combined with --watch");
+            process::exit(1);
+        }
+
+        Self {
+            path,
+            include_vendor,
+            include,
+            exclude,
+            extensions,
+            threads,
+            cache,
+            no_cache,
+            clear_cache,
+            watch,
+            interval,
+            config,
+            check,
+            format,
+            baseline,
+            update_baseline,
+            prune_baseline,
+            save_scan,
+            diff,
+            changed_since,
+            trend,
+            output,
+            report,
+            functions,
+            functions_by,
+            no_tui,
+            filter,
+            sort,
+            show,
+        }
+    }
+
+    /// The cache file, defaulting to one in the scanned directory.
+    pub fn cache_path(&self) -> Option<PathBuf> {
+        if self.no_cache {
+            return None;
+        }
+        let default = || PathBuf::from(&self.path).join(".tcplscan.cache");
+        Some(self.cache.clone().unwrap_or_else(default))
+    }
+}
+
+fn parse_value(option: &str, value: Option<String>) -> String {
+    value.unwrap_or_else(|| {
+        eprintln!("ERROR: Missing value for {option}");
+        process::exit(1);
+    })
+}
+
+/// Parses a value the same way the config file spells it.
+fn parse_enum<T: for<'de> Deserialize<'de>>(option: &str, value: Option<String>) -> T {
+    let value = parse_value(option, value);
+    let deserializer: serde::de::value::StrDeserializer<serde::de::value::Error> =
+        value.as_str().into_deserializer();
+    T::deserialize(deserializer).unwrap_or_else(|err| {
+        eprintln!("ERROR: Invalid value for {option}, {err}");
+        process::exit(1);
+    })
+}
+
+fn parse_number(option: &str, value: Option<String>) -> usize {
+    let value = parse_value(option, value);
+    match value.parse::<usize>() {
+        Ok(number) if number > 0 => number,
+        _ => {
+            eprintln!("ERROR: {option} expects a positive number, got {value}");
+            process::exit(1);
+        }
+    }
+}
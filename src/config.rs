@@ -0,0 +1,216 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use serde::Deserialize;
+
+use crate::interface::SortOrder;
+
+pub const CONFIG_FILE: &str = "tcplscan.toml";
+
+/// Project settings from a tcplscan.toml, anything given on the command line wins.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Directories to scan, relative to the config file. Defaults to the scanned directory.
+    pub paths: Vec<String>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub extensions: Option<Vec<String>>,
+    pub include_vendor: bool,
+    pub sort: Option<SortOrder>,
+    pub format: Option<OutputFormat>,
+    pub view: ViewConfig,
+    pub thresholds: Thresholds,
+    pub layers: Vec<Layer>,
+    /// Violations accepted by `--check`, relative to the config file.
+    pub baseline: Option<String>,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewConfig {
+    pub top_files: Option<usize>,
+    pub dependencies: Option<bool>,
+    pub functions: Option<usize>,
+    pub function_statements: Option<bool>,
+}
+
+/// Limits checked by `--check`, a missing limit isn't checked.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    pub function_complexity: Option<usize>,
+    pub average_complexity: Option<f64>,
+    pub dependencies: Option<usize>,
+    pub lines: Option<usize>,
+    /// Report classes that depend on each other in a circle
+    pub cycles: bool,
+}
+
+/// Classes in `namespace` may only depend on their own layer, the layers named in
+/// `allow` and classes outside of any layer.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Layer {
+    pub name: String,
+    pub namespace: String,
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Sarif,
+    Checkstyle,
+    Junit,
+    Html,
+    Markdown,
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                OutputFormat::Text => "text",
+                OutputFormat::Json => "json",
+                OutputFormat::Csv => "csv",
+                OutputFormat::Sarif => "sarif",
+                OutputFormat::Checkstyle => "checkstyle",
+                OutputFormat::Junit => "junit",
+                OutputFormat::Html => "html",
+                OutputFormat::Markdown => "markdown",
+            }
+        )
+    }
+}
+
+impl Config {
+    /// Loads the given config file, or the tcplscan.toml in the scanned directory if there is one.
+    pub fn discover(scan_path: &str, explicit: Option<&Path>) -> Option<Self> {
+        let path = match explicit {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let path = Path::new(scan_path).join(CONFIG_FILE);
+                if !path.is_file() {
+                    return None;
+                }
+                path
+            }
+        };
+        Some(Self::load(&path))
+    }
+
+    fn load(path: &Path) -> Self {
+        let content = fs::read_to_string(path).unwrap_or_else(|err| {
+            eprintln!("ERROR: Failed to read config {}, {err}", path.display());
+            process::exit(1);
+        });
+        let mut config = Self::parse(&content).unwrap_or_else(|err| {
+            eprintln!("ERROR: Invalid config {}, {err}", path.display());
+            process::exit(1);
+        });
+        config.dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        config
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let config: Config = toml::from_str(content).map_err(|err| err.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks what serde can't, naming the offending key.
+    fn validate(&self) -> Result<(), String> {
+        if self.extensions.as_ref().is_some_and(|e| e.is_empty()) {
+            return Err("extensions: at least one extension is required".to_string());
+        }
+        if self.view.top_files == Some(0) {
+            return Err("view.top_files: must be at least 1".to_string());
+        }
+        let mut names = HashSet::new();
+        for (i, layer) in self.layers.iter().enumerate() {
+            if !names.insert(layer.name.as_str()) {
+                return Err(format!("layers[{i}].name: duplicate layer {}", layer.name));
+            }
+            if layer.namespace.trim_matches('\\').is_empty() {
+                return Err(format!("layers[{i}].namespace: must not be empty"));
+            }
+        }
+        for (i, layer) in self.layers.iter().enumerate() {
+            for allowed in layer.allow.iter() {
+                if !names.contains(allowed.as_str()) {
+                    return Err(format!("layers[{i}].allow: unknown layer {allowed}"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The configured scan paths resolved against the config file's directory.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.paths.iter().map(|path| self.dir.join(path)).collect()
+    }
+
+    pub fn baseline(&self) -> Option<PathBuf> {
+        self.baseline.as_ref().map(|path| self.dir.join(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::SortType;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            r#"
+            sort = "function-complexity"
+
+            [view]
+            top_files = 20
+
+            [thresholds]
+            function_complexity = 10
+
+            [[layers]]
+            name = "Domain"
+            namespace = "App\\Domain"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            Some(SortOrder::from(SortType::FunctionComplexity)),
+            config.sort
+        );
+        assert_eq!(Some(20), config.view.top_files);
+        assert_eq!(Some(10), config.thresholds.function_complexity);
+        assert_eq!("App\\Domain", config.layers[0].namespace);
+    }
+
+    #[test]
+    fn test_errors_name_the_key() {
+        let err = Config::parse("[thresholds]\nfunction_complexiy = 10").unwrap_err();
+        assert!(err.contains("function_complexiy"), "{err}");
+
+        let err = Config::parse(
+            "[[layers]]\nname = \"Domain\"\nnamespace = \"App\"\nallow = [\"Infra\"]",
+        )
+        .unwrap_err();
+        assert_eq!("layers[0].allow: unknown layer Infra", err);
+    }
+}
@@ -0,0 +1,125 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::indexing::parser::Class;
+
+// Bump whenever `Class` or the parser output changes so stale caches get thrown away
+const CACHE_VERSION: u32 = 5;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheEntry {
+    pub modified: u64,
+    pub size: u64,
+    pub lines: usize,
+    /// None for PHP files without a class so they aren't re-parsed either
+    pub class: Option<Class>,
+}
+
+/// Parsed classes from a previous scan, keyed by path.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cache {
+    version: u32,
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Self {
+            version: CACHE_VERSION,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Loads the cache, starting from an empty one when it is missing, unreadable or
+    /// written by a different cache version.
+    pub fn load(path: &Path) -> Self {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => return Self::new(),
+        };
+        match serde_json::from_str::<Cache>(&content) {
+            Ok(cache) if cache.version == CACHE_VERSION => cache,
+            Ok(_) => {
+                eprintln!("Cache was written by another version of tcplscan, rebuilding it.");
+                Self::new()
+            }
+            Err(err) => {
+                eprintln!("ERROR: Failed to parse cache {}, {err}", path.display());
+                Self::new()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) {
+        let content = match serde_json::to_string(self) {
+            Ok(content) => content,
+            Err(err) => {
+                eprintln!("ERROR: Failed to serialize cache, {err}");
+                return;
+            }
+        };
+        if let Err(err) = fs::write(path, content) {
+            eprintln!("ERROR: Failed to write cache {}, {err}", path.display());
+        }
+    }
+
+    /// Returns the cached entry if the file hasn't changed since it was parsed.
+    pub fn get(&self, path: &str, modified: u64, size: u64) -> Option<&CacheEntry> {
+        self.entries
+            .get(path)
+            .filter(|entry| entry.modified == modified && entry.size == size)
+    }
+
+    pub fn insert(&mut self, path: String, entry: CacheEntry) {
+        self.entries.insert(path, entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let path = env::temp_dir().join(format!("tcplscan-cache-{}.json", process::id()));
+        let mut class = Class::new();
+        class.name = "App\\Invoice".to_string();
+        let mut cache = Cache::new();
+        let entry = |class| CacheEntry {
+            modified: 100,
+            size: 2048,
+            lines: 80,
+            class,
+        };
+        cache.insert("src/Invoice.php".to_string(), entry(Some(class.clone())));
+        cache.insert("src/helpers.php".to_string(), entry(None));
+        cache.save(&path);
+
+        let cache = Cache::load(&path);
+        let cached = cache.get("src/Invoice.php", 100, 2048).unwrap();
+        assert_eq!(Some(&class), cached.class.as_ref());
+        assert_eq!(80, cached.lines);
+        assert!(cache
+            .get("src/helpers.php", 100, 2048)
+            .unwrap()
+            .class
+            .is_none());
+        assert!(cache.get("src/Invoice.php", 101, 2048).is_none());
+        assert!(cache.get("src/Invoice.php", 100, 2049).is_none());
+        assert!(cache.get("src/Missing.php", 100, 2048).is_none());
+
+        let content = fs::read_to_string(&path).unwrap().replacen(
+            &format!("\"version\":{CACHE_VERSION}"),
+            "\"version\":0",
+            1,
+        );
+        fs::write(&path, content).unwrap();
+        assert!(Cache::load(&path)
+            .get("src/Invoice.php", 100, 2048)
+            .is_none());
+        fs::remove_file(&path).unwrap();
+    }
+}
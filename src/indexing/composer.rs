@@ -0,0 +1,364 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Default)]
+struct Manifest {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    autoload: AutoloadSection,
+    #[serde(default, rename = "autoload-dev")]
+    autoload_dev: AutoloadSection,
+    #[serde(default)]
+    config: ManifestConfig,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ManifestConfig {
+    #[serde(default, rename = "vendor-dir")]
+    vendor_dir: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct AutoloadSection {
+    #[serde(default, rename = "psr-4")]
+    psr4: HashMap<String, Paths>,
+    #[serde(default, rename = "psr-0")]
+    psr0: HashMap<String, Paths>,
+    #[serde(default)]
+    classmap: Vec<String>,
+}
+
+// Composer allows either a single directory or a list of them
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Paths {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Paths {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Paths::One(path) => vec![path],
+            Paths::Many(paths) => paths,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct Lock {
+    #[serde(default)]
+    packages: Vec<LockPackage>,
+    #[serde(default, rename = "packages-dev")]
+    packages_dev: Vec<LockPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockPackage {
+    name: String,
+    #[serde(default)]
+    autoload: AutoloadSection,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum AutoloadKind {
+    Psr4,
+    Psr0,
+    Classmap,
+}
+
+#[derive(Debug)]
+struct AutoloadRule {
+    kind: AutoloadKind,
+    prefix: String,
+    dir: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct Package {
+    pub name: String,
+    namespaces: Vec<String>,
+}
+
+/// What we know about a project from its composer.json and composer.lock.
+#[derive(Debug)]
+pub struct Composer {
+    pub name: String,
+    vendor_dir: PathBuf,
+    autoload: Vec<AutoloadRule>,
+    pub packages: Vec<Package>,
+}
+
+impl Composer {
+    /// Looks for a composer.json in the scanned directory and then in its parents.
+    pub fn discover(path: &str) -> Option<Self> {
+        let start = fs::canonicalize(path).ok()?;
+        let root = start
+            .ancestors()
+            .find(|dir| dir.join("composer.json").is_file())?;
+        Some(Self::load(root))
+    }
+
+    fn load(root: &Path) -> Self {
+        let manifest: Manifest = read_json(&root.join("composer.json"));
+        let lock: Lock = if root.join("composer.lock").is_file() {
+            read_json(&root.join("composer.lock"))
+        } else {
+            Lock::default()
+        };
+
+        let mut autoload = Vec::new();
+        for section in [manifest.autoload, manifest.autoload_dev] {
+            for (prefix, paths) in section.psr4 {
+                for dir in paths.into_vec() {
+                    autoload.push(AutoloadRule {
+                        kind: AutoloadKind::Psr4,
+                        prefix: normalize(&prefix),
+                        dir: canonical(root.join(dir)),
+                    });
+                }
+            }
+            for (prefix, paths) in section.psr0 {
+                for dir in paths.into_vec() {
+                    autoload.push(AutoloadRule {
+                        kind: AutoloadKind::Psr0,
+                        prefix: normalize(&prefix),
+                        dir: canonical(root.join(dir)),
+                    });
+                }
+            }
+            for dir in section.classmap {
+                autoload.push(AutoloadRule {
+                    kind: AutoloadKind::Classmap,
+                    prefix: String::new(),
+                    dir: canonical(root.join(dir)),
+                });
+            }
+        }
+        // Longest directories first so nested autoload roots win
+        autoload.sort_by_key(|rule| std::cmp::Reverse(rule.dir.as_os_str().len()));
+
+        let packages = lock
+            .packages
+            .into_iter()
+            .chain(lock.packages_dev)
+            .map(|package| {
+                let namespaces = package
+                    .autoload
+                    .psr4
+                    .into_keys()
+                    .chain(package.autoload.psr0.into_keys())
+                    .map(|prefix| normalize(&prefix))
+                    .filter(|prefix| !prefix.is_empty())
+                    .collect();
+                Package {
+                    name: package.name,
+                    namespaces,
+                }
+            })
+            .collect();
+
+        let vendor_dir = root.join(manifest.config.vendor_dir.as_deref().unwrap_or("vendor"));
+        Self {
+            name: manifest.name.unwrap_or_else(|| "root".to_string()),
+            vendor_dir: canonical(vendor_dir),
+            autoload,
+            packages,
+        }
+    }
+
+    pub fn vendor_dir(&self) -> &Path {
+        &self.vendor_dir
+    }
+
+    /// Finds the package owning a class by the longest matching namespace prefix.
+    pub fn package_for(&self, class_name: &str) -> Option<&str> {
+        let class_name = class_name.trim_start_matches('\\');
+        let own = self
+            .autoload
+            .iter()
+            .filter(|rule| rule.kind != AutoloadKind::Classmap)
+            .map(|rule| (rule.prefix.as_str(), self.name.as_str()));
+        let vendor = self.packages.iter().flat_map(|package| {
+            package
+                .namespaces
+                .iter()
+                .map(|namespace| (namespace.as_str(), package.name.as_str()))
+        });
+        own.chain(vendor)
+            .filter(|(prefix, _)| !prefix.is_empty() && in_namespace(class_name, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, name)| name)
+    }
+
+    fn rule_for(&self, path: &Path) -> Option<(&AutoloadRule, Vec<String>)> {
+        let path = fs::canonicalize(path).ok()?;
+        let rule = self
+            .autoload
+            .iter()
+            .find(|rule| path.starts_with(&rule.dir))?;
+        let segments = path
+            .strip_prefix(&rule.dir)
+            .ok()?
+            .with_extension("")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        Some((rule, segments))
+    }
+
+    /// Returns the class name the autoloader expects at `path` when the declared one
+    /// doesn't match it. Classmap directories can hold any class.
+    pub fn autoload_mismatch(&self, path: &Path, class_name: &str) -> Option<String> {
+        let (rule, segments) = self.rule_for(path)?;
+        let declared = class_name.trim_start_matches('\\');
+        let (expected, matches) = match rule.kind {
+            AutoloadKind::Psr4 => {
+                let mut expected = rule.prefix.clone();
+                if !expected.is_empty() {
+                    expected.push('\\');
+                }
+                expected.push_str(&segments.join("\\"));
+                let matches = declared == expected;
+                (expected, matches)
+            }
+            // PSR-0 keeps the full namespace in the directory structure and
+            // maps underscores in the class name to directories
+            AutoloadKind::Psr0 => {
+                let expected = segments.join("\\");
+                let (namespace, name) = declared.rsplit_once('\\').unwrap_or(("", declared));
+                let mut resolved = namespace.to_string();
+                if !resolved.is_empty() {
+                    resolved.push('\\');
+                }
+                resolved.push_str(&name.replace('_', "\\"));
+                let matches = resolved == expected;
+                (expected, matches)
+            }
+            AutoloadKind::Classmap => return None,
+        };
+        (!matches).then_some(expected)
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> T {
+    let content = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("ERROR: Failed to read {}, {err}", path.display());
+        process::exit(1);
+    });
+    serde_json::from_str(&content).unwrap_or_else(|err| {
+        eprintln!("ERROR: Failed to parse {}, {err}", path.display());
+        process::exit(1);
+    })
+}
+
+fn canonical(path: PathBuf) -> PathBuf {
+    fs::canonicalize(&path).unwrap_or(path)
+}
+
+fn normalize(prefix: &str) -> String {
+    prefix.trim_matches('\\').to_string()
+}
+
+fn in_namespace(class_name: &str, prefix: &str) -> bool {
+    class_name == prefix
+        || class_name
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('\\'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn composer() -> Composer {
+        Composer {
+            name: "acme/app".to_string(),
+            vendor_dir: PathBuf::from("/vendor"),
+            autoload: vec![AutoloadRule {
+                kind: AutoloadKind::Psr4,
+                prefix: "App".to_string(),
+                dir: PathBuf::from("/src"),
+            }],
+            packages: vec![
+                Package {
+                    name: "symfony/http-foundation".to_string(),
+                    namespaces: vec!["Symfony\\Component\\HttpFoundation".to_string()],
+                },
+                Package {
+                    name: "symfony/symfony".to_string(),
+                    namespaces: vec!["Symfony".to_string()],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_package_for() {
+        let composer = composer();
+        assert_eq!(
+            Some("acme/app"),
+            composer.package_for("\\App\\Billing\\Invoice")
+        );
+        assert_eq!(
+            Some("symfony/http-foundation"),
+            composer.package_for("Symfony\\Component\\HttpFoundation\\Request")
+        );
+        assert_eq!(
+            Some("symfony/symfony"),
+            composer.package_for("Symfony\\Component\\Console\\Command")
+        );
+        assert_eq!(None, composer.package_for("\\Application\\Foo"));
+        assert_eq!(None, composer.package_for("\\DateTime"));
+    }
+
+    #[test]
+    fn test_autoload_mismatch() {
+        let root = std::env::temp_dir().join(format!("tcplscan-composer-{}", process::id()));
+        for path in [
+            "src/Billing/Invoice.php",
+            "lib/Acme/Legacy/Old/Thing.php",
+            "scripts/seed.php",
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "<?php\n").unwrap();
+        }
+        let root = canonical(root);
+        let rule = |kind, prefix: &str, dir| AutoloadRule {
+            kind,
+            prefix: prefix.to_string(),
+            dir: root.join(dir),
+        };
+        let composer = Composer {
+            autoload: vec![
+                rule(AutoloadKind::Psr4, "App", "src"),
+                rule(AutoloadKind::Psr0, "Acme", "lib"),
+            ],
+            ..composer()
+        };
+        let mismatch = |path, class_name| composer.autoload_mismatch(&root.join(path), class_name);
+
+        assert_eq!(
+            None,
+            mismatch("src/Billing/Invoice.php", "App\\Billing\\Invoice")
+        );
+        assert_eq!(
+            Some("App\\Billing\\Invoice".to_string()),
+            mismatch("src/Billing/Invoice.php", "\\App\\Invoice")
+        );
+        assert_eq!(
+            None,
+            mismatch("lib/Acme/Legacy/Old/Thing.php", "Acme\\Legacy\\Old_Thing")
+        );
+        assert_eq!(None, mismatch("scripts/seed.php", "App\\Seed"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
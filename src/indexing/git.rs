@@ -0,0 +1,230 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::indexing::{parse_source, File};
+
+/// How often and by how many people a file was changed, from `git log`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    pub commits: usize,
+    pub authors: usize,
+    /// Seconds since the Unix epoch
+    pub last_commit: u64,
+}
+
+/// Runs git in `dir` and returns its output.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run git, {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Files that differ from `base` in the working tree, including untracked ones.
+fn changed_files(path: &Path, base: &str) -> Result<HashSet<PathBuf>, String> {
+    let root = PathBuf::from(git(path, &["rev-parse", "--show-toplevel"])?.trim());
+    let changed = git(&root, &["diff", "--name-only", base, "--"])?;
+    let untracked = git(&root, &["ls-files", "--others", "--exclude-standard"])?;
+    Ok(changed
+        .lines()
+        .chain(untracked.lines())
+        .filter_map(|name| fs::canonicalize(root.join(name)).ok())
+        .collect())
+}
+
+/// The content of a file as of `base`, None if it didn't exist yet.
+fn show(path: &Path, base: &str) -> Option<String> {
+    let dir = path.parent()?;
+    let name = path.file_name()?.to_str()?;
+    git(dir, &["show", &format!("{base}:./{name}")]).ok()
+}
+
+/// Marks which functions of the classes changed since `base` differ from `base`, and
+/// returns the paths of those files. The classes themselves are kept whole so their
+/// metrics stay the same.
+pub fn mark_changed(path: &str, base: &str, files: &mut [File]) -> Result<HashSet<String>, String> {
+    let changed = changed_files(Path::new(path), base)?;
+    let mut paths = HashSet::new();
+    for file in files.iter_mut() {
+        if !fs::canonicalize(&file.path).is_ok_and(|path| changed.contains(&path)) {
+            continue;
+        }
+        paths.insert(file.path.to_owned());
+        // Every function counts as changed when the old version is missing or doesn't parse
+        let before = show(Path::new(&file.path), base)
+            .and_then(|content| parse_source(&content).ok().flatten());
+        let Some((before, _)) = before else {
+            continue;
+        };
+        let changed = file
+            .class
+            .functions
+            .iter()
+            .filter(|function| !before.functions.iter().any(|old| old.same_as(function)))
+            .map(|function| function.name.to_owned())
+            .collect();
+        file.changed_functions = Some(changed);
+    }
+    Ok(paths)
+}
+
+/// The history of every file ever committed, keyed by canonical path. None when `path`
+/// isn't inside a git repository.
+pub fn histories(path: &Path) -> Option<HashMap<PathBuf, History>> {
+    let root = PathBuf::from(git(path, &["rev-parse", "--show-toplevel"]).ok()?.trim());
+    let log = match git(&root, &["log", "--format=%x00%at %aE", "--name-only"]) {
+        Ok(log) => log,
+        Err(err) => {
+            // A repository without commits has no history yet
+            if !err.contains("does not have any commits") {
+                eprintln!("ERROR: Failed to read the git history, {err}");
+            }
+            return None;
+        }
+    };
+
+    let mut histories: HashMap<&str, (History, HashSet<&str>)> = HashMap::new();
+    let mut commit = (0, "");
+    for line in log.lines() {
+        if let Some(header) = line.strip_prefix('\0') {
+            let (time, author) = header.split_once(' ').unwrap_or((header, ""));
+            commit = (time.parse().unwrap_or(0), author);
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let (history, authors) = histories.entry(line).or_insert_with(|| {
+            let history = History {
+                commits: 0,
+                authors: 0,
+                last_commit: 0,
+            };
+            (history, HashSet::new())
+        });
+        history.commits += 1;
+        history.last_commit = history.last_commit.max(commit.0);
+        authors.insert(commit.1);
+        history.authors = authors.len();
+    }
+
+    Some(
+        histories
+            .into_iter()
+            .filter_map(|(name, (history, _))| {
+                let path = fs::canonicalize(root.join(name)).ok()?;
+                Some((path, history))
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub hash: String,
+    /// Committer date in ISO 8601
+    pub date: String,
+}
+
+/// The commits in a range like `v1.0..main`, oldest first, following first parents only.
+pub fn commits(path: &Path, range: &str) -> Result<Vec<Commit>, String> {
+    let log = git(
+        path,
+        &[
+            "log",
+            "--reverse",
+            "--first-parent",
+            "--format=%H %cI",
+            range,
+            "--",
+        ],
+    )?;
+    Ok(log
+        .lines()
+        .filter_map(|line| {
+            let (hash, date) = line.split_once(' ')?;
+            Some(Commit {
+                hash: hash.to_string(),
+                date: date.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// A detached checkout in a temporary directory, removed again when dropped. Checking
+/// out another commit only rewrites the files that differ, so the cache stays valid for
+/// the rest.
+pub struct Worktree {
+    repository: PathBuf,
+    dir: PathBuf,
+    /// The scanned directory relative to the repository root
+    prefix: String,
+}
+
+impl Worktree {
+    pub fn new(path: &Path, commit: &str) -> Result<Self, String> {
+        let prefix = git(path, &["rev-parse", "--show-prefix"])?
+            .trim()
+            .to_string();
+        let dir = env::temp_dir().join(format!("tcplscan-{}", process::id()));
+        fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+        let tree = dir.join("tree");
+        git(
+            path,
+            &[
+                "worktree",
+                "add",
+                "--detach",
+                "--quiet",
+                tree.to_str().unwrap_or_default(),
+                commit,
+            ],
+        )?;
+        Ok(Self {
+            repository: path.to_path_buf(),
+            dir,
+            prefix,
+        })
+    }
+
+    pub fn checkout(&self, commit: &str) -> Result<(), String> {
+        git(
+            &self.dir.join("tree"),
+            &["checkout", "--detach", "--quiet", commit],
+        )
+        .map(|_| ())
+    }
+
+    /// The scanned directory inside the checkout.
+    pub fn path(&self) -> PathBuf {
+        self.dir.join("tree").join(&self.prefix)
+    }
+
+    /// A cache file next to the checkout, so it isn't scanned itself.
+    pub fn cache_path(&self) -> PathBuf {
+        self.dir.join("cache")
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let tree = self.dir.join("tree");
+        let tree = tree.to_str().unwrap_or_default();
+        if let Err(err) = git(&self.repository, &["worktree", "remove", "--force", tree]) {
+            eprintln!("ERROR: Failed to remove worktree {tree}, {err}");
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -1,147 +1,393 @@
 use std::{
     collections::{HashMap, VecDeque},
-    fs::{self, ReadDir},
-    process,
-    time::SystemTime,
+    fs,
+    path::{Path, PathBuf},
+    sync::{mpsc, Mutex},
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
-use crate::indexing::{parser::Parser, tokenizer::Tokenizer};
+use crate::indexing::{
+    cache::{Cache, CacheEntry},
+    parser::Parser,
+    tokenizer::Tokenizer,
+    walk::walk,
+};
+
+use serde::{Deserialize, Serialize};
 
 use self::parser::Class;
 
+pub use self::composer::Composer;
+pub use self::git::{commits, mark_changed, History, Worktree};
+pub use self::parser::Function;
+pub use self::scan::{load_scan, save_scan};
+pub use self::watch::{Change, Watcher};
+
+mod cache;
+mod composer;
+mod git;
 mod parser;
+mod scan;
 mod token;
 mod tokenizer;
+mod walk;
+mod watch;
 
 pub type ClassDependencyIndex = HashMap<String, usize>;
 
-#[derive(Debug)]
+pub struct IndexOptions<'a> {
+    pub composer: Option<&'a Composer>,
+    pub include_vendor: bool,
+    /// Directories to walk, the scanned directory when empty
+    pub paths: Vec<PathBuf>,
+    /// Globs relative to the scanned directory, when any are given only matching files are scanned
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub extensions: Vec<String>,
+    pub threads: usize,
+    /// Where parsed classes are cached between runs, None disables the cache
+    pub cache_path: Option<PathBuf>,
+    pub clear_cache: bool,
+    /// Whether to read the git history of every file
+    pub histories: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct File {
     pub path: String,
     pub class: Class,
     pub lines: usize,
-    pub last_accessed: usize,
+    /// Seconds since the Unix epoch
+    pub modified: u64,
+    /// None when the file isn't committed to a git repository
+    pub history: Option<History>,
+    pub autoload_mismatch: Option<String>,
+    /// Names of the functions changed since the `--changed-since` revision, None when
+    /// every function counts
+    #[serde(default)]
+    pub changed_functions: Option<Vec<String>>,
 }
 
+/// A PHP file found while walking the directory. Its content is only read once a
+/// parser thread picks it up and is dropped as soon as the class has been parsed.
 #[derive(Debug)]
-struct RawFile {
+struct SourceFile {
     path: String,
-    content: Vec<char>,
-    last_accessed: usize,
+    /// Nanoseconds since the Unix epoch
+    modified: u64,
+    size: u64,
 }
 
 impl File {
-    fn new(path: &str, class: Class, lines: usize, last_accessed: usize) -> Self {
+    fn new(path: &str, class: Class, lines: usize, modified: u64) -> Self {
         Self {
             path: path.to_string(),
             class,
             lines,
-            last_accessed,
+            modified,
+            history: None,
+            autoload_mismatch: None,
+            changed_functions: None,
         }
     }
+
+    /// Whether `function` is reported, false for functions left unchanged since the
+    /// `--changed-since` revision.
+    pub fn is_changed(&self, function: &Function) -> bool {
+        self.changed_functions
+            .as_ref()
+            .is_none_or(|names| names.contains(&function.name))
+    }
 }
 
-fn read_dir(dir_entry: ReadDir, files: &mut Vec<RawFile>) {
-    dir_entry.for_each(|entry| {
-        let entry = entry.unwrap_or_else(|err| {
-            eprintln!("ERROR: Failed to parse directory entry, {err}");
-            process::exit(1);
-        });
-        let metadata = entry.metadata().unwrap_or_else(|err| {
-            eprintln!("ERROR: Failed to parse directory entry, {err}");
-            process::exit(1);
+#[cfg(test)]
+impl File {
+    /// A file at `src/<name>.php` declaring an empty class with the given dependencies.
+    pub fn test(name: &str, dependencies: &[&str]) -> Self {
+        let mut class = Class::new();
+        class.name = name.to_string();
+        class.line = 1;
+        class.dependencies = dependencies.iter().map(|d| d.to_string()).collect();
+        Self::new(&format!("src/{name}.php"), class, 10, 0)
+    }
+}
+
+/// Parses a file unless the cache has it, adding the time spent reading and parsing
+/// to `timings`. Returns whether the class came from the cache.
+fn parse_file(
+    source: &SourceFile,
+    composer: Option<&Composer>,
+    cache: &Cache,
+    timings: &mut Timings,
+) -> (Result<Option<File>, String>, bool) {
+    if let Some(entry) = cache.get(&source.path, source.modified, source.size) {
+        let file = entry
+            .class
+            .clone()
+            .map(|class| new_file(source, class, entry.lines, composer));
+        return (Ok(file), true);
+    }
+    let now = Instant::now();
+    let content = read_source(source);
+    timings.read += now.elapsed();
+    let now = Instant::now();
+    let file = content.and_then(|content| parse_content(source, &content, composer));
+    timings.parse += now.elapsed();
+    (file, false)
+}
+
+/// The file's class, None if it doesn't declare one.
+fn read_and_parse(
+    source: &SourceFile,
+    composer: Option<&Composer>,
+) -> Result<Option<File>, String> {
+    let content = read_source(source)?;
+    parse_content(source, &content, composer)
+}
+
+fn read_source(source: &SourceFile) -> Result<String, String> {
+    fs::read_to_string(&source.path).map_err(|err| {
+        format!(
+            "Failed to read file with path: {:?}, err: {:?}",
+            source.path, err
+        )
+    })
+}
+
+fn parse_content(
+    source: &SourceFile,
+    content: &str,
+    composer: Option<&Composer>,
+) -> Result<Option<File>, String> {
+    let parsed = parse_source(content)
+        .map_err(|err| format!("Failed to parse file with path: {:?}, {err}", source.path))?;
+    Ok(parsed.map(|(class, lines)| new_file(source, class, lines, composer)))
+}
+
+/// Parses PHP source into its class and the number of lines, None if it declares no class.
+fn parse_source(content: &str) -> Result<Option<(Class, usize)>, String> {
+    let tokens = Tokenizer::new(content).collect::<Result<VecDeque<_>, _>>()?;
+    let lines = match tokens.back() {
+        Some(token) => token.line,
+        None => 0,
+    };
+    let class = Parser::new().parse_file(tokens)?;
+    Ok(class.map(|class| (class, lines)))
+}
+
+fn new_file(source: &SourceFile, class: Class, lines: usize, composer: Option<&Composer>) -> File {
+    let mut file = File::new(
+        source.path.as_str(),
+        class,
+        lines,
+        source.modified / 1_000_000_000,
+    );
+    if let Some(composer) = composer {
+        file.autoload_mismatch =
+            composer.autoload_mismatch(Path::new(&file.path), &file.class.name);
+    }
+    file
+}
+
+struct Parsed {
+    source: SourceFile,
+    file: Result<Option<File>, String>,
+    cached: bool,
+}
+
+/// Time the parser threads spent in each phase, summed over the threads.
+#[derive(Default)]
+struct Timings {
+    read: Duration,
+    parse: Duration,
+}
+
+/// Streams files from a directory walker to `options.threads` parser threads through a
+/// bounded channel, so only a handful of files are ever held in memory at once.
+fn parse_files(
+    path: &str,
+    options: &IndexOptions,
+    cache: &Cache,
+) -> (Vec<Parsed>, usize, bool, Timings) {
+    let threads = options.threads.max(1);
+    let (sender, receiver) = mpsc::sync_channel::<(usize, SourceFile)>(threads * 4);
+    let receiver = Mutex::new(receiver);
+    let (mut parsed, found, vendor_skipped, timings) = thread::scope(|scope| {
+        // Numbering the files lets us put them back in order however the threads interleave
+        let walker = scope.spawn(|| {
+            let mut found = 0;
+            let vendor_skipped = walk(path, options, &mut |file| {
+                // The parser threads only hang up once they're all done
+                let _ = sender.send((found, file));
+                found += 1;
+            });
+            drop(sender);
+            (found, vendor_skipped)
         });
-        if metadata.is_file() {
-            let path = entry.path();
-            match path.as_path().extension() {
-                Some(extension) => {
-                    if extension != "php" {
-                        return;
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut parsed = Vec::new();
+                    let mut timings = Timings::default();
+                    loop {
+                        let next = receiver.lock().unwrap().recv();
+                        let (i, source) = match next {
+                            Ok(next) => next,
+                            Err(_) => break,
+                        };
+                        let (file, cached) =
+                            parse_file(&source, options.composer, cache, &mut timings);
+                        parsed.push((
+                            i,
+                            Parsed {
+                                source,
+                                file,
+                                cached,
+                            },
+                        ));
                     }
-                }
-                None => return,
-            }
-            let content = match fs::read_to_string(&path) {
-                Ok(content) => content.chars().collect::<Vec<_>>(),
-                Err(err) => {
-                    eprintln!(
-                        "ERROR: Failed to read file with path: {:?}, err: {:?}",
-                        path, err
-                    );
-                    return;
-                }
-            };
-            let now = SystemTime::now();
-            let accessed = metadata.accessed().unwrap_or_else(|err| {
-                eprintln!("ERROR: Failed to read accessed date, {err}");
-                process::exit(1);
-            });
-            let last_accessed = now.duration_since(accessed).unwrap().as_secs() / 3600;
-            let file = RawFile {
-                path: path.into_os_string().into_string().unwrap(),
-                content,
-                last_accessed: last_accessed as usize,
-            };
-            files.push(file);
-        }
-        if metadata.is_dir() {
-            let path = entry.path();
-            let dir_entry = fs::read_dir(path).unwrap_or_else(|err| {
-                eprintln!("ERROR: Failed to read directory, {err}");
-                process::exit(1);
-            });
-            read_dir(dir_entry, files);
+                    (parsed, timings)
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut parsed = Vec::new();
+        let mut timings = Timings::default();
+        for worker in workers {
+            let (worker_parsed, worker_timings) = worker.join().unwrap();
+            parsed.extend(worker_parsed);
+            timings.read += worker_timings.read;
+            timings.parse += worker_timings.parse;
         }
+        let (found, vendor_skipped) = walker.join().unwrap();
+        (parsed, found, vendor_skipped, timings)
     });
+    parsed.sort_by_key(|(i, _)| *i);
+    let parsed = parsed.into_iter().map(|(_, parsed)| parsed).collect();
+    (parsed, found, vendor_skipped, timings)
 }
 
-pub fn index(dir_entry: ReadDir) -> (ClassDependencyIndex, Vec<File>) {
-    let mut files: Vec<File> = Vec::new();
-    let mut raw_files: Vec<RawFile> = Vec::new();
+fn add_to_index(index: &mut ClassDependencyIndex, class: &Class) {
+    index.entry(class.name.to_owned()).or_insert(0);
+    for dependency in class.dependencies.iter() {
+        index
+            .entry(dependency.to_owned())
+            .and_modify(|c| *c += 1)
+            .or_insert(1);
+    }
+}
 
-    let now = SystemTime::now();
-    read_dir(dir_entry, &mut raw_files);
-    let diff = now.elapsed().unwrap().as_millis() as f64;
-    println!(
-        "Filtered out and read {} files in {:.4} seconds.",
-        raw_files.len(),
-        diff / 1000.0
-    );
+fn remove_from_index(index: &mut ClassDependencyIndex, class: &Class) {
+    for dependency in class.dependencies.iter() {
+        if let Some(count) = index.get_mut(dependency) {
+            *count -= 1;
+            if *count == 0 {
+                index.remove(dependency);
+            }
+        }
+    }
+    if index.get(&class.name) == Some(&0) {
+        index.remove(&class.name);
+    }
+}
+
+fn build_index(files: &[File]) -> ClassDependencyIndex {
+    let mut index = ClassDependencyIndex::new();
+    for file in files.iter() {
+        add_to_index(&mut index, &file.class);
+    }
+    index
+}
+
+pub fn index(path: &str, options: &IndexOptions) -> (ClassDependencyIndex, Vec<File>) {
+    let cache = match &options.cache_path {
+        Some(path) if !options.clear_cache => Cache::load(path),
+        _ => Cache::new(),
+    };
 
-    let mut parser = Parser::new();
     let now = SystemTime::now();
-    raw_files.iter().for_each(|file| {
-        let tokens = Tokenizer::new(&file.content).collect::<VecDeque<_>>();
-        let line = match tokens.back() {
-            Some(token) => token.line,
-            None => 0,
+    let (parsed, found, vendor_skipped, timings) = parse_files(path, options, &cache);
+    drop(cache);
+    let mut files = Vec::new();
+    let mut cache = Cache::new();
+    let mut reused = 0;
+    for parsed in parsed {
+        if parsed.cached {
+            reused += 1;
+        }
+        // Files that don't parse are skipped, and left out of the cache so the next run
+        // reports them again
+        let file = match parsed.file {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("ERROR: {err}");
+                continue;
+            }
         };
-        if let Some(class) = parser.parse_file(tokens) {
-            let file = File::new(file.path.as_str(), class, line, file.last_accessed);
+        if options.cache_path.is_some() {
+            let entry = CacheEntry {
+                modified: parsed.source.modified,
+                size: parsed.source.size,
+                lines: file.as_ref().map_or(0, |file| file.lines),
+                class: file.as_ref().map(|file| file.class.clone()),
+            };
+            cache.insert(parsed.source.path, entry);
+        }
+        if let Some(file) = file {
             files.push(file);
         }
-    });
+    }
     let diff = now.elapsed().unwrap().as_millis() as f64;
-    println!(
-        "Finished scanning and parsing {} files in {:.4} seconds.",
+    // Each thread's wall-clock time added up, the threads read and parse at the same time
+    // so these come to more than the total below. Cached files are neither read nor parsed.
+    let read = found - reused;
+    eprintln!(
+        "Read {read} files in {:.4} seconds of thread time.",
+        timings.read.as_secs_f64()
+    );
+    eprintln!(
+        "Scanned and parsed {read} files in {:.4} seconds of thread time on {} threads.",
+        timings.parse.as_secs_f64(),
+        options.threads
+    );
+    eprintln!(
+        "Read, scanned and parsed {} of {found} files in {:.4} seconds.",
         files.len(),
         diff / 1000.0
     );
+    if let Some(path) = &options.cache_path {
+        eprintln!("Reused {reused} unchanged files from the cache.");
+        cache.save(path);
+    }
+    if vendor_skipped {
+        eprintln!("Skipped the vendor directory, use --include-vendor to scan it.");
+    }
+    let mismatches = files
+        .iter()
+        .filter(|file| file.autoload_mismatch.is_some())
+        .count();
+    if mismatches > 0 {
+        eprintln!("Found {mismatches} classes that don't match their autoload path.");
+    }
 
-    let now = SystemTime::now();
-    let mut index = ClassDependencyIndex::new();
-    for file in files.iter() {
-        let class = &file.class;
-        index.entry(class.name.to_owned()).or_insert(0);
-        for dependency in class.dependencies.iter() {
-            index
-                .entry(dependency.to_owned())
-                .and_modify(|c| *c += 1)
-                .or_insert(1);
+    let histories = options
+        .histories
+        .then(|| git::histories(Path::new(path)))
+        .flatten();
+    if let Some(histories) = histories {
+        for file in files.iter_mut() {
+            file.history = fs::canonicalize(&file.path)
+                .ok()
+                .and_then(|path| histories.get(&path).cloned());
         }
+        let committed = files.iter().filter(|file| file.history.is_some()).count();
+        eprintln!("Read the git history of {committed} files.");
     }
+
+    let now = SystemTime::now();
+    let index = build_index(&files);
     let diff = now.elapsed().unwrap().as_millis() as f64;
-    println!("Indexed classes in {:.4} seconds", diff / 1000.0);
+    eprintln!("Indexed classes in {:.4} seconds", diff / 1000.0);
 
     (index, files)
 }
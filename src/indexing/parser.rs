@@ -1,15 +1,16 @@
 use std::{
     collections::VecDeque,
     fmt::{self, Display, Formatter},
-    process,
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::indexing::{
     token::{match_data_type, match_keyword, Keyword, TokenType},
     tokenizer::Token,
 };
 
-#[derive(Debug, Eq, Hash, PartialEq, Clone)]
+#[derive(Debug, Eq, Hash, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Stmt {
     pub kind: StmtType,
     pub line: usize,
@@ -37,7 +38,23 @@ impl Stmt {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+impl Display for Stmt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            StmtType::If => write!(f, "if")?,
+            StmtType::Elseif => write!(f, "elseif")?,
+            StmtType::For => write!(f, "for")?,
+            StmtType::Foreach => write!(f, "foreach")?,
+            StmtType::Throw => write!(f, "throw")?,
+            StmtType::Catch => write!(f, "catch")?,
+            StmtType::Switch { case_count, .. } => write!(f, "switch with {case_count} cases")?,
+            StmtType::Match { case_count } => write!(f, "match with {case_count} arms")?,
+        }
+        write!(f, " on line {}", self.line)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
 pub enum StmtType {
     If,
     Elseif,
@@ -61,25 +78,31 @@ impl Alias {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
 pub struct Class {
     pub name: String,
     pub functions: Vec<Function>,
     pub extends: Option<String>,
     pub implements: Vec<String>,
+    /// Traits pulled in with `use` inside the class
+    pub traits: Vec<String>,
     pub is_abstract: bool,
     pub dependencies: Vec<String>,
+    /// Line of the class declaration
+    pub line: usize,
 }
 
 impl Class {
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             name: String::new(),
             functions: Vec::new(),
             extends: None,
             implements: Vec::new(),
+            traits: Vec::new(),
             is_abstract: false,
             dependencies: Vec::new(),
+            line: 0,
         }
     }
 
@@ -130,7 +153,7 @@ impl Class {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum Visibility {
     Public,
     Private,
@@ -151,7 +174,7 @@ impl Display for Visibility {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
 pub struct Function {
     pub stmts: Vec<Stmt>,
     pub name: String,
@@ -159,6 +182,15 @@ pub struct Function {
     pub return_type: Option<String>,
     pub visibility: Visibility,
     pub is_abstract: bool,
+    /// Line of the function declaration
+    pub line: usize,
+    /// Lines from the declaration to the closing brace
+    pub length: usize,
+    /// Deepest nesting of braces inside the body
+    pub nesting: usize,
+    /// Hash of the tokens from the parameters to the closing brace, to tell whether
+    /// the source changed even when the function moved
+    pub hash: u64,
 }
 
 impl Function {
@@ -169,6 +201,7 @@ impl Function {
         return_type: Option<String>,
         visibility: Visibility,
         is_abstract: bool,
+        line: usize,
     ) -> Self {
         Self {
             name,
@@ -177,6 +210,10 @@ impl Function {
             return_type,
             visibility,
             is_abstract,
+            line,
+            length: 1,
+            nesting: 0,
+            hash: 0,
         }
     }
 
@@ -187,78 +224,129 @@ impl Function {
         }
         sum + 1
     }
+
+    /// Lines of the statements that add to the complexity, including nested ones.
+    pub fn branch_lines(&self) -> Vec<usize> {
+        fn collect(stmts: &[Stmt], lines: &mut Vec<usize>) {
+            for stmt in stmts.iter() {
+                lines.push(stmt.line);
+                if let StmtType::Switch { stmts, .. } = &stmt.kind {
+                    collect(stmts, lines);
+                }
+            }
+        }
+        let mut lines = Vec::new();
+        collect(&self.stmts, &mut lines);
+        lines
+    }
+
+    /// Whether both are the same function, even if it moved to other lines.
+    pub fn same_as(&self, other: &Function) -> bool {
+        self.name == other.name && self.hash == other.hash
+    }
 }
 
-pub struct Parser {
-    tokens: VecDeque<Token>,
+#[cfg(test)]
+impl Function {
+    /// A public function whose body is enough `if` statements to reach `complexity`.
+    pub fn test(name: &str, complexity: usize) -> Self {
+        let stmts = (1..complexity)
+            .map(|line| Stmt::new(StmtType::If, line))
+            .collect();
+        Self::new(
+            name.to_string(),
+            stmts,
+            0,
+            None,
+            Visibility::Public,
+            false,
+            1,
+        )
+    }
+}
+
+pub struct Parser<'a> {
+    tokens: VecDeque<Token<'a>>,
     brackets: VecDeque<TokenType>,
-    pub classes: Vec<Class>,
     namespace: String,
     uses: Vec<String>,
     aliases: Vec<Alias>,
+    // Open braces, and the most there have been since a function body started
+    braces: usize,
+    deepest_brace: usize,
+    // Line of the last token read
+    line: usize,
+    // FNV-1a hash of the tokens read while parsing a function
+    hash: Option<u64>,
 }
 
-impl Parser {
+impl<'a> Parser<'a> {
     pub fn new() -> Self {
         Self {
             tokens: VecDeque::new(),
             brackets: VecDeque::new(),
-            classes: Vec::new(),
             namespace: String::new(),
             uses: Vec::new(),
             aliases: Vec::new(),
+            braces: 0,
+            deepest_brace: 0,
+            line: 0,
+            hash: None,
         }
     }
 
-    fn closing_bracket(&mut self, token_type: TokenType) {
-        let top = self.brackets.pop_back().unwrap_or_else(|| {
-            eprintln!("Unmatched opening bracket: {:?}", token_type);
-            process::exit(1);
-        });
-        match top {
-            TokenType::LeftParen => {
-                if token_type != TokenType::RightParen {
-                    eprintln!("Unmatched closing bracket: {:?}", token_type);
-                    process::exit(1);
-                }
-            }
-            TokenType::LeftBrace => {
-                if token_type != TokenType::RightBrace {
-                    eprintln!("Unmatched closing bracket: {:?}", token_type);
-                    process::exit(1);
-                }
-            }
-            TokenType::LeftBracket => {
-                if token_type != TokenType::RightBracket {
-                    eprintln!("Unmatched closing bracket: {:?}", token_type);
-                    process::exit(1);
-                }
-            }
-            _ => {
-                panic!("This shouldn't happen :P");
-            }
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("line {}: {message}", self.line))
+    }
+
+    fn closing_bracket(&mut self, token_type: TokenType) -> Result<(), String> {
+        let opening = match token_type {
+            TokenType::RightParen => TokenType::LeftParen,
+            TokenType::RightBrace => TokenType::LeftBrace,
+            TokenType::RightBracket => TokenType::LeftBracket,
+            _ => panic!("This shouldn't happen :P"),
+        };
+        match self.brackets.pop_back() {
+            Some(top) if top == opening => Ok(()),
+            _ => self.error(&format!("Unmatched closing bracket: {:?}", token_type)),
         }
     }
 
-    fn next_token_opt(&mut self) -> Option<Token> {
-        let token = self.tokens.pop_front()?;
+    fn next_token_opt(&mut self) -> Result<Option<Token<'a>>, String> {
+        let Some(token) = self.tokens.pop_front() else {
+            return Ok(None);
+        };
+        self.line = token.line;
+        if let Some(hash) = self.hash.as_mut() {
+            // The separator keeps `ab c` and `a bc` apart
+            for byte in token.lexeme.bytes().chain([0]) {
+                *hash = (*hash ^ byte as u64).wrapping_mul(0x100000001b3);
+            }
+        }
         match token.token_type {
             TokenType::LeftParen => self.brackets.push_back(TokenType::LeftParen),
-            TokenType::LeftBrace => self.brackets.push_back(TokenType::LeftBrace),
+            TokenType::LeftBrace => {
+                self.brackets.push_back(TokenType::LeftBrace);
+                self.braces += 1;
+                self.deepest_brace = self.deepest_brace.max(self.braces);
+            }
             TokenType::LeftBracket => self.brackets.push_back(TokenType::LeftBracket),
-            TokenType::RightParen => self.closing_bracket(TokenType::RightParen),
-            TokenType::RightBrace => self.closing_bracket(TokenType::RightBrace),
-            TokenType::RightBracket => self.closing_bracket(TokenType::RightBracket),
+            TokenType::RightParen => self.closing_bracket(TokenType::RightParen)?,
+            TokenType::RightBrace => {
+                self.closing_bracket(TokenType::RightBrace)?;
+                self.braces -= 1;
+            }
+            TokenType::RightBracket => self.closing_bracket(TokenType::RightBracket)?,
             _ => (),
         }
-        Some(token)
+        Ok(Some(token))
     }
 
-    fn next_token(&mut self) -> Token {
-        self.next_token_opt().unwrap_or_else(|| {
-            eprintln!("Expected token. Found none.");
-            process::exit(1);
-        })
+    fn next_token(&mut self) -> Result<Token<'a>, String> {
+        match self.next_token_opt()? {
+            Some(token) => Ok(token),
+            None => self.error("Expected token. Found none."),
+        }
     }
 
     fn next_matches_token_types(&self, token_types: &[TokenType]) -> bool {
@@ -273,64 +361,69 @@ impl Parser {
             .is_some_and(|t| match_keyword(t).is_some_and(|kw| keywords.contains(&kw)))
     }
 
-    fn peek(&self) -> Option<&Token> {
+    fn peek(&self) -> Option<&Token<'a>> {
         self.tokens.front()
     }
 
-    fn synchronize(&mut self) {
+    fn synchronize(&mut self) -> Result<(), String> {
         while self
             .peek()
             .is_some_and(|t| t.token_type != TokenType::Semicolon)
         {
-            self.next_token();
+            self.next_token()?;
         }
-        self.next_token();
+        self.next_token()?;
+        Ok(())
     }
 }
 
-impl Parser {
-    pub fn parse_file(&mut self, tokens: VecDeque<Token>) -> Option<Class> {
+impl<'a> Parser<'a> {
+    /// The first class, trait or abstract class declared in the tokens, if any.
+    pub fn parse_file(&mut self, tokens: VecDeque<Token<'a>>) -> Result<Option<Class>, String> {
         self.tokens = tokens;
+        self.namespace.clear();
         self.brackets.clear();
         self.uses.clear();
         self.aliases.clear();
-        while let Some(token) = self.next_token_opt() {
+        self.braces = 0;
+        self.hash = None;
+        while let Some(token) = self.next_token_opt()? {
             match token.token_type {
                 TokenType::Identifier => {
                     if let Some(token_type) = match_keyword(&token) {
                         match token_type {
                             Keyword::Namespace => {
-                                self.namespace = self.next_token().lexeme;
+                                self.namespace = self.next_token()?.lexeme.to_string();
                                 continue;
                             }
                             // When adding these as dependencies need to switch it back from alias
                             // to actual type
                             Keyword::Use => {
-                                let name = self.next_token().lexeme;
+                                let name = self.next_token()?.lexeme;
                                 if self.next_matches_keywords(&[Keyword::As]) {
-                                    self.next_token();
-                                    let aliased = self.next_token().lexeme;
+                                    self.next_token()?;
+                                    let aliased = self.next_token()?.lexeme;
                                     let mut split: Vec<_> = name.split('\\').collect();
                                     split.pop();
-                                    split.push(aliased.as_str());
+                                    split.push(aliased);
                                     split.join("\\");
                                     self.uses.push(split.join("\\"));
                                     self.aliases
                                         .push(Alias::new(name.to_owned(), split.join("\\")));
                                     continue;
                                 }
-                                self.uses.push(name);
+                                self.uses.push(name.to_string());
                                 continue;
                             }
                             Keyword::Abstract => {
-                                self.next_token();
-                                return Some(self.class(true));
+                                self.next_token()?;
+                                return Ok(Some(self.class(true)?));
                             }
                             Keyword::Class => {
-                                return Some(self.class(false));
+                                return Ok(Some(self.class(false)?));
                             }
                             Keyword::Trait => {
-                                return Some(self.class(false));
+                                return Ok(Some(self.class(false)?));
                             }
                             _ => continue,
                         }
@@ -341,8 +434,8 @@ impl Parser {
                             TokenType::ColonColon,
                             TokenType::ThinArrow,
                         ]) {
-                            self.next_token();
-                            self.next_token();
+                            self.next_token()?;
+                            self.next_token()?;
                         }
                         continue;
                     }
@@ -350,37 +443,39 @@ impl Parser {
                 _ => continue,
             };
         }
-        None
+        Ok(None)
     }
 
-    fn class(&mut self, is_abstract: bool) -> Class {
+    fn class(&mut self, is_abstract: bool) -> Result<Class, String> {
         let mut class = Class::new();
         class.is_abstract = is_abstract;
         class.name.push_str(self.namespace.as_str());
         class.name.push('\\');
-        class.name.push_str(self.next_token().lexeme.as_str());
+        let name = self.next_token()?;
+        class.name.push_str(name.lexeme);
+        class.line = name.line;
         if self.next_matches_keywords(&[Keyword::Extends]) {
-            self.next_token();
-            let extends = self.next_token();
+            self.next_token()?;
+            let extends = self.next_token()?;
             class.extends = Some(self.find_type(&extends));
         }
         if self.next_matches_keywords(&[Keyword::Implements]) {
-            self.next_token();
+            self.next_token()?;
             while self
                 .peek()
                 .is_some_and(|t| t.token_type != TokenType::LeftBrace)
             {
-                let implements = self.next_token();
+                let implements = self.next_token()?;
                 if implements.token_type == TokenType::Comma {
                     continue;
                 }
-                class.implements.push(implements.lexeme);
+                class.implements.push(implements.lexeme.to_string());
             }
         }
         let depth = self.brackets.len();
-        self.next_token();
+        self.next_token()?;
         while depth != self.brackets.len() {
-            self.statement(&mut class);
+            self.statement(&mut class)?;
         }
         for usage in self.uses.iter() {
             if !class.dependencies.contains(usage) {
@@ -397,39 +492,47 @@ impl Parser {
         class
             .functions
             .sort_by_key(|f| std::cmp::Reverse(f.complexity()));
-        class
+        Ok(class)
     }
 
-    fn statement(&mut self, class: &mut Class) {
-        let token = self.next_token();
+    fn statement(&mut self, class: &mut Class) -> Result<(), String> {
+        let token = self.next_token()?;
         if let Some(keyword) = match_keyword(&token) {
             match keyword {
-                Keyword::Abstract => self.statement(class),
-                Keyword::Use => {
-                    let token = self.next_token();
-                    class.add_dependency(self.find_type(&token));
-                }
-                _ => self.match_keyword(class, token),
+                Keyword::Abstract => self.statement(class)?,
+                Keyword::Use => loop {
+                    let token = self.next_token()?;
+                    let name = self.find_type(&token);
+                    let name = name.trim_start_matches('\\').to_owned();
+                    class.traits.push(name.clone());
+                    class.add_dependency(name);
+                    if !self.next_matches_token_types(&[TokenType::Comma]) {
+                        break;
+                    }
+                    self.next_token()?;
+                },
+                _ => self.match_keyword(class, token)?,
             };
         }
+        Ok(())
     }
 
-    fn match_keyword(&mut self, class: &mut Class, token: Token) {
+    fn match_keyword(&mut self, class: &mut Class, token: Token) -> Result<(), String> {
         let mut token = token;
         let mut keyword = match match_keyword(&token) {
             Some(keyword) => keyword,
-            None => return,
+            None => return Ok(()),
         };
         let visibility = if let Keyword::Visibility(parsed_visiblity) = keyword {
-            token = self.next_token();
+            token = self.next_token()?;
             // If this is a type we're returning early
             if let Some(data_type) = self.parse_type(&token) {
                 class.add_dependency(data_type);
-                return;
+                return Ok(());
             }
             keyword = match match_keyword(&token) {
                 Some(keyword) => keyword,
-                None => return,
+                None => return Ok(()),
             };
             parsed_visiblity
         } else {
@@ -437,9 +540,9 @@ impl Parser {
         };
         match keyword {
             // Handle this later
-            Keyword::Const => self.synchronize(),
+            Keyword::Const => self.synchronize()?,
             Keyword::Readonly => {
-                let token = self.next_token();
+                let token = self.next_token()?;
                 // Must be a type if it is readonly
                 if let Some(dependency) = self.parse_type(&token) {
                     class.add_dependency(dependency);
@@ -447,7 +550,7 @@ impl Parser {
             }
             Keyword::Static => {
                 // Ignore the static token
-                let token = self.next_token();
+                let token = self.next_token()?;
                 let keyword = match match_keyword(&token) {
                     Some(keyword) => keyword,
                     None => {
@@ -455,28 +558,29 @@ impl Parser {
                         if let Some(custom_type) = self.parse_type(&token) {
                             class.add_dependency(custom_type);
                         }
-                        return;
+                        return Ok(());
                     }
                 };
                 if keyword == Keyword::Function {
-                    let function = self.function(Visibility::Public, class);
+                    let function = self.function(Visibility::Public, class)?;
                     class.add_fn(function);
-                    return;
+                    return Ok(());
                 }
                 if let Some(data_type) = self.parse_type(&token) {
                     class.add_dependency(data_type);
                 }
             }
             Keyword::Function => {
-                let function = self.function(visibility, class);
+                let function = self.function(visibility, class)?;
                 class.add_fn(function);
             }
             // Just a type
             _ => match self.parse_type(&token) {
                 Some(dependency) => class.add_dependency(dependency),
-                None => self.synchronize(),
+                None => self.synchronize()?,
             },
         }
+        Ok(())
     }
 
     fn parse_type(&mut self, token: &Token) -> Option<String> {
@@ -495,13 +599,15 @@ impl Parser {
         Some(self.find_type(token))
     }
 
-    fn function(&mut self, visibility: Visibility, class: &mut Class) -> Function {
-        let name = self.next_token().lexeme;
+    fn function(&mut self, visibility: Visibility, class: &mut Class) -> Result<Function, String> {
+        let name_token = self.next_token()?;
+        let (name, line) = (name_token.lexeme.to_string(), name_token.line);
+        self.hash = Some(0xcbf29ce484222325);
         let depth = self.brackets.len();
-        self.next_token();
+        self.next_token()?;
         let mut params = 0;
         while self.brackets.len() != depth {
-            let token = self.next_token();
+            let token = self.next_token()?;
             // Handle this properly pls
             if [TokenType::Comma, TokenType::RightParen, TokenType::Equal]
                 .contains(&token.token_type)
@@ -517,30 +623,47 @@ impl Parser {
             }
         }
         let return_type = if self.next_matches_token_types(&[TokenType::Colon]) {
-            self.next_token();
-            let mut return_token = self.next_token();
+            self.next_token()?;
+            let mut return_token = self.next_token()?;
             // TODO handle this nullable thing
             // Also need to handle unions and &, so all this
             // will need to be parsed using a custom grammar
             if return_token.token_type == TokenType::Question {
-                return_token = self.next_token();
+                return_token = self.next_token()?;
             }
             Some(self.find_type(&return_token))
         } else {
             None
         };
         let depth = self.brackets.len();
-        let token = self.next_token();
+        let braces = self.braces;
+        self.deepest_brace = braces;
+        let token = self.next_token()?;
         if token.token_type == TokenType::Semicolon {
-            return Function::new(name, Vec::new(), params, return_type, visibility, true);
+            let mut function = Function::new(
+                name,
+                Vec::new(),
+                params,
+                return_type,
+                visibility,
+                true,
+                line,
+            );
+            function.hash = self.hash.take().unwrap_or_default();
+            return Ok(function);
         }
         let mut stmts = Vec::new();
         while depth != self.brackets.len() {
-            if let Some(stmt) = self.parse_stmt() {
+            if let Some(stmt) = self.parse_stmt()? {
                 stmts.push(stmt);
             }
         }
-        Function::new(name, stmts, params, return_type, visibility, false)
+        let mut function = Function::new(name, stmts, params, return_type, visibility, false, line);
+        function.length = self.line - line + 1;
+        // The body's own braces don't count as nesting
+        function.nesting = self.deepest_brace.saturating_sub(braces + 1);
+        function.hash = self.hash.take().unwrap_or_default();
+        Ok(function)
     }
 
     fn find_type(&mut self, type_token: &Token) -> String {
@@ -553,8 +676,11 @@ impl Parser {
         }
         let mut data_type = String::new();
         for use_stmt in self.uses.iter() {
-            let ending = use_stmt.split('\\').last().expect("Empty use statement");
-            if type_token.lexeme.as_str() == ending {
+            let ending = use_stmt
+                .split('\\')
+                .next_back()
+                .expect("Empty use statement");
+            if type_token.lexeme == ending {
                 data_type.push_str(use_stmt.as_str());
                 break;
             }
@@ -562,53 +688,51 @@ impl Parser {
         if data_type.is_empty() {
             data_type.push_str(self.namespace.as_str());
             data_type.push('\\');
-            data_type.push_str(type_token.lexeme.as_str());
+            data_type.push_str(type_token.lexeme);
         }
         data_type
     }
 
-    fn parse_stmt(&mut self) -> Option<Stmt> {
-        let token = self.next_token();
+    fn parse_stmt(&mut self) -> Result<Option<Stmt>, String> {
+        let token = self.next_token()?;
         if token.token_type != TokenType::Identifier {
             // hack to get over things like $this->match()
             if [TokenType::ColonColon, TokenType::ThinArrow].contains(&token.token_type) {
-                self.next_token();
-                self.next_token();
-                self.next_token();
+                self.next_token()?;
+                self.next_token()?;
+                self.next_token()?;
             }
-            return None;
+            return Ok(None);
         }
-        let keyword = match match_keyword(&token) {
-            Some(keyword) => keyword,
-            None => return None,
+        let Some(keyword) = match_keyword(&token) else {
+            return Ok(None);
         };
         let line = token.line;
         self.create_statement(keyword, line)
     }
 
-    fn create_statement(&mut self, keyword: Keyword, line: usize) -> Option<Stmt> {
-        Some(match keyword {
+    fn create_statement(&mut self, keyword: Keyword, line: usize) -> Result<Option<Stmt>, String> {
+        Ok(Some(match keyword {
             Keyword::If => Stmt::new(StmtType::If, line),
             Keyword::Elseif => Stmt::new(StmtType::Elseif, line),
             Keyword::For => Stmt::new(StmtType::For, line),
             Keyword::Foreach => Stmt::new(StmtType::Foreach, line),
-            Keyword::Switch => self.switch_stmt(line),
-            Keyword::Match => self.match_stmt(line),
+            Keyword::Switch => self.switch_stmt(line)?,
+            Keyword::Match => self.match_stmt(line)?,
             Keyword::Throw => Stmt::new(StmtType::Throw, line),
             Keyword::Catch => Stmt::new(StmtType::Catch, line),
-            _ => return None,
-        })
+            _ => return Ok(None),
+        }))
     }
 
-    fn switch_stmt(&mut self, line: usize) -> Stmt {
+    fn switch_stmt(&mut self, line: usize) -> Result<Stmt, String> {
         let mut case_count = 0;
         let depth = self.brackets.len();
         let mut stmts = Vec::new();
         loop {
-            let token = self.next_token_opt().unwrap_or_else(|| {
-                eprintln!("Unterminated switch statement");
-                process::exit(1);
-            });
+            let Some(token) = self.next_token_opt()? else {
+                return self.error("Unterminated switch statement");
+            };
 
             match token.token_type {
                 TokenType::Identifier => {
@@ -623,7 +747,7 @@ impl Parser {
                         }
                         _ => {
                             let line = token.line;
-                            match self.create_statement(keyword, line) {
+                            match self.create_statement(keyword, line)? {
                                 Some(stmt) => stmt,
                                 None => continue,
                             }
@@ -638,24 +762,23 @@ impl Parser {
                 _ => continue,
             }
         }
-        Stmt::new(StmtType::Switch { case_count, stmts }, line)
+        Ok(Stmt::new(StmtType::Switch { case_count, stmts }, line))
     }
 
-    fn match_stmt(&mut self, line: usize) -> Stmt {
+    fn match_stmt(&mut self, line: usize) -> Result<Stmt, String> {
         let mut case_count = 0;
         let depth = self.brackets.len();
         loop {
-            let token = self.next_token_opt().unwrap_or_else(|| {
-                eprintln!("Unterminated match statement");
-                process::exit(1);
-            });
+            let Some(token) = self.next_token_opt()? else {
+                return self.error("Unterminated match statement");
+            };
 
             match token.token_type {
                 TokenType::LeftBracket => {
                     let depth = self.brackets.len() - 1;
-                    self.next_token();
+                    self.next_token()?;
                     while self.brackets.len() != depth {
-                        self.next_token();
+                        self.next_token()?;
                     }
                 }
                 TokenType::FatArrow => case_count += 1,
@@ -667,6 +790,73 @@ impl Parser {
                 _ => continue,
             }
         }
-        Stmt::new(StmtType::Match { case_count }, line)
+        Ok(Stmt::new(StmtType::Match { case_count }, line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexing::tokenizer::Tokenizer;
+
+    #[test]
+    fn test_function_length_and_nesting() {
+        let code = "<?php\nclass Foo\n{\n    public function bar($a)\n    {\n        if ($a) {\n            foreach ($a as $b) { return; }\n        }\n    }\n    abstract function baz();\n}\n";
+        let class = Parser::new()
+            .parse_file(Tokenizer::new(code).map(Result::unwrap).collect())
+            .unwrap()
+            .unwrap();
+        let bar = class.functions.iter().find(|f| f.name == "bar").unwrap();
+        assert_eq!((4, 6, 2), (bar.line, bar.length, bar.nesting));
+        let baz = class.functions.iter().find(|f| f.name == "baz").unwrap();
+        assert_eq!((1, 0), (baz.length, baz.nesting));
+    }
+
+    #[test]
+    fn test_same_as_compares_source() {
+        let parse = |code: &str| {
+            Parser::new()
+                .parse_file(Tokenizer::new(code).map(Result::unwrap).collect())
+                .unwrap()
+                .unwrap()
+                .functions
+                .remove(0)
+        };
+        let function = parse("<?php\nclass Foo { function bar($a) { if ($a) { return 1; } } }");
+        let moved =
+            parse("<?php\n\n\nclass Foo {\n function bar($a)\n {\n if ($a) { return 1; }\n }\n}");
+        let edited = parse("<?php\nclass Foo { function bar($a) { if ($a) { return 2; } } }");
+        assert!(function.same_as(&moved));
+        assert!(!function.same_as(&edited));
+    }
+
+    #[test]
+    fn test_unbalanced_brackets() {
+        let parse = |code: &str| {
+            Parser::new().parse_file(Tokenizer::new(code).map(Result::unwrap).collect())
+        };
+        assert_eq!(
+            Err("line 3: Unmatched closing bracket: RightParen".to_string()),
+            parse("<?php\nclass Foo {\n function bar() { if ($a)) {} }\n}")
+        );
+        assert_eq!(
+            Err("line 2: Expected token. Found none.".to_string()),
+            parse("<?php\nclass Foo { function bar() {")
+        );
+    }
+
+    #[test]
+    fn test_use_several_traits() {
+        let code = "<?php\nnamespace App;\nuse Lib\\Loggable;\nclass Foo\n{\n    use Loggable, Cacheable;\n    use \\Lib\\Timestamps;\n}\n";
+        let class = Parser::new()
+            .parse_file(Tokenizer::new(code).map(Result::unwrap).collect())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            vec!["Lib\\Loggable", "App\\Cacheable", "Lib\\Timestamps"],
+            class.traits
+        );
+        assert!(class.dependencies.contains(&"App\\Cacheable".to_string()));
+        assert!(class.dependencies.contains(&"Lib\\Timestamps".to_string()));
     }
 }
@@ -0,0 +1,52 @@
+use std::{fs, path::Path, process};
+
+use serde::{Deserialize, Serialize};
+
+use crate::indexing::{build_index, ClassDependencyIndex, File};
+
+// Bump whenever `File` or `Class` changes so old scans are rejected instead of misread
+const SCAN_VERSION: u32 = 6;
+
+/// The files of a scan saved with `--save-scan`, so it can be compared later.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedScan<F> {
+    version: u32,
+    files: F,
+}
+
+pub fn save_scan(path: &Path, files: &[File]) {
+    let scan = SavedScan {
+        version: SCAN_VERSION,
+        files,
+    };
+    let content = serde_json::to_string(&scan).unwrap_or_else(|err| {
+        eprintln!("ERROR: Failed to serialize scan, {err}");
+        process::exit(1);
+    });
+    if let Err(err) = fs::write(path, content) {
+        eprintln!("ERROR: Failed to write scan {}, {err}", path.display());
+        process::exit(1);
+    }
+}
+
+pub fn load_scan(path: &Path) -> (ClassDependencyIndex, Vec<File>) {
+    let content = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("ERROR: Failed to read scan {}, {err}", path.display());
+        process::exit(1);
+    });
+    let scan = match serde_json::from_str::<SavedScan<Vec<File>>>(&content) {
+        Ok(scan) if scan.version == SCAN_VERSION => scan,
+        Ok(_) => {
+            eprintln!(
+                "ERROR: Scan {} was saved by another version of tcplscan",
+                path.display()
+            );
+            process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("ERROR: Failed to parse scan {}, {err}", path.display());
+            process::exit(1);
+        }
+    };
+    (build_index(&scan.files), scan.files)
+}
@@ -70,14 +70,14 @@ pub fn match_keyword(token: &Token) -> Option<Keyword> {
     if token.token_type != TokenType::Identifier {
         return None;
     }
-    KEYWORDS.get(token.lexeme.as_str()).copied()
+    KEYWORDS.get(token.lexeme).copied()
 }
 
 pub fn match_data_type(token: &Token) -> Option<Keyword> {
     if token.token_type != TokenType::Identifier {
         return None;
     }
-    BUILT_IN_DATA_TYPES.get(token.lexeme.as_str()).copied()
+    BUILT_IN_DATA_TYPES.get(token.lexeme).copied()
 }
 
 #[derive(Debug, PartialEq, Hash, Eq, Copy, Clone)]
@@ -1,17 +1,15 @@
-use std::process;
-
-use crate::error;
 use crate::indexing::token::TokenType;
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct Token {
+/// A token borrowing its lexeme from the source, so tokenizing doesn't allocate.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Token<'a> {
     pub token_type: TokenType,
     pub line: usize,
-    pub lexeme: String,
+    pub lexeme: &'a str,
 }
 
-impl Token {
-    fn new(token_type: TokenType, line: usize, lexeme: String) -> Self {
+impl<'a> Token<'a> {
+    fn new(token_type: TokenType, line: usize, lexeme: &'a str) -> Self {
         Self {
             token_type,
             line,
@@ -20,148 +18,172 @@ impl Token {
     }
 }
 
+/// Scans the source as bytes. Every byte we branch on is ASCII so token boundaries
+/// always fall on character boundaries and lexemes can be sliced straight out of it.
 pub struct Tokenizer<'a> {
-    pub code: &'a [char],
+    source: &'a str,
+    current: usize,
+    start: usize,
     pub line: usize,
 }
 
 impl<'a> Tokenizer<'a> {
-    pub fn new(code: &'a [char]) -> Self {
-        Self { code, line: 1 }
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            current: 0,
+            start: 0,
+            line: 1,
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
     }
 
     fn advance(&mut self) {
-        if let Some(c) = self.code.first() {
-            if c == &'\n' {
+        if let Some(c) = self.peek() {
+            if c == b'\n' {
                 self.line += 1;
             }
 
-            self.code = &self.code[1..];
-        } else {
-            error("Expected token", self.line);
+            self.current += 1;
         }
     }
 
-    fn next_char_opt(&mut self) -> Option<char> {
-        let char = match self.code.first() {
-            Some(char) => char,
-            None => return None,
-        };
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("line {}: {message}", self.line))
+    }
+
+    fn next_char_opt(&mut self) -> Option<u8> {
+        let char = self.peek()?;
         self.advance();
-        Some(*char)
+        Some(char)
     }
 
-    fn next_char(&mut self) -> char {
-        self.next_char_opt().unwrap_or_else(|| {
-            error("Character missing", self.line);
-            process::exit(1);
-        })
+    fn next_char(&mut self) -> Result<u8, String> {
+        match self.next_char_opt() {
+            Some(char) => Ok(char),
+            None => self.error("Character missing"),
+        }
     }
 
-    fn peek(&self) -> Option<&char> {
-        self.code.first()
+    fn peek(&self) -> Option<u8> {
+        self.source.as_bytes().get(self.current).copied()
     }
 
-    fn peek_next(&self) -> Option<&char> {
-        self.code.get(1)
+    fn peek_next(&self) -> Option<u8> {
+        self.source.as_bytes().get(self.current + 1).copied()
     }
 
-    fn match_char(&mut self, to_match: char) -> bool {
-        if self.peek().is_some_and(|char| char == &to_match) {
+    fn match_char(&mut self, to_match: u8) -> bool {
+        if self.peek().is_some_and(|char| char == to_match) {
             self.advance();
             return true;
         }
         false
     }
 
-    fn make_token(&self, token_type: TokenType, lexeme: String) -> Token {
-        Token::new(token_type, self.line, lexeme)
+    fn make_token(&self, token_type: TokenType) -> Token<'a> {
+        self.make_token_from(token_type, self.start, self.current)
     }
 
-    fn string(&mut self, quote_type: char) -> Token {
-        let mut string = String::new();
+    fn make_token_from(&self, token_type: TokenType, start: usize, end: usize) -> Token<'a> {
+        Token::new(token_type, self.line, &self.source[start..end])
+    }
+
+    fn string(&mut self, quote_type: u8) -> Result<Token<'a>, String> {
+        let start = self.current;
         let mut escaped = false;
-        while (!self.peek().is_some_and(|c| c == &quote_type) || escaped) && !self.code.is_empty() {
-            let char = self.next_char();
-            if char == '\\' {
+        while (self.peek().is_none_or(|c| c != quote_type) || escaped) && !self.is_at_end() {
+            let char = self.next_char()?;
+            if char == b'\\' {
                 escaped = !escaped;
             } else {
                 escaped = false;
             }
-            string.push(char);
         }
-        if self.code.is_empty() {
-            error("Unterminated string", self.line);
+        if self.is_at_end() {
+            return self.error("Unterminated string");
         }
+        let end = self.current;
         self.advance();
 
-        self.make_token(TokenType::String, string)
+        Ok(self.make_token_from(TokenType::String, start, end))
     }
 
-    fn number(&mut self) -> Token {
-        let mut string = String::new();
+    fn number(&mut self) -> Token<'a> {
         while self.peek().is_some_and(|c| c.is_ascii_digit()) {
-            string.push(self.next_char());
+            self.advance();
         }
-        if self.peek().is_some_and(|c| c == &'.') {
-            string.push(self.next_char());
+        if self.peek().is_some_and(|c| c == b'.') {
+            self.advance();
             while self.peek().is_some_and(|c| c.is_ascii_digit()) {
-                string.push(self.next_char());
+                self.advance();
             }
         }
 
-        self.make_token(TokenType::Number, string)
+        self.make_token(TokenType::Number)
     }
 
-    fn identifier(&mut self, start: char) -> Token {
-        let mut word = start.to_string();
+    fn identifier(&mut self) -> Token<'a> {
         while self
             .peek()
-            .is_some_and(|c| c.is_alphanumeric() || c == &'_' || c == &'\\')
+            .is_some_and(|c| is_identifier_char(c) || c.is_ascii_digit() || c == b'\\')
         {
-            word.push(self.next_char());
+            self.advance();
         }
 
-        self.make_token(TokenType::Identifier, word)
+        self.make_token(TokenType::Identifier)
     }
 
-    fn here_doc(&mut self) -> Token {
-        let mut title = Vec::new();
-        let mut doc = String::new();
-        if self.peek().is_some_and(|c| c == &'\'' || c == &'"') {
-            let opening = self.next_char();
-            while self.peek().is_some_and(|c| c != &opening) {
-                title.push(self.next_char());
+    fn here_doc(&mut self) -> Result<Token<'a>, String> {
+        let title_start;
+        let title_end;
+        if self.peek().is_some_and(|c| c == b'\'' || c == b'"') {
+            let opening = self.next_char()?;
+            title_start = self.current;
+            while self.peek().is_some_and(|c| c != opening) {
+                self.advance();
             }
+            title_end = self.current;
             self.advance();
         } else {
-            while self.peek().is_some_and(|c| c != &'\n') {
-                title.push(self.next_char());
+            title_start = self.current;
+            while self.peek().is_some_and(|c| c != b'\n') {
+                self.advance();
             }
+            title_end = self.current;
         }
-        self.advance();
+        let title = &self.source[title_start..title_end];
+        // Anything else after the title could leave the body starting inside a character
+        self.match_char(b'\r');
+        if !self.match_char(b'\n') {
+            return self.error("Expected a new line after the heredoc title");
+        }
+        let doc_start = self.current;
         loop {
-            let mut found = true;
-            for (i, char) in title.iter().enumerate() {
-                if &self.code[i] != char {
-                    found = false;
-                    break;
-                }
-            }
-            if found {
-                title.iter().for_each(|_| self.advance());
-                break;
+            // Compare bytes, `current` can be inside a multi-byte character of the body
+            if self.source.as_bytes()[self.current..].starts_with(title.as_bytes()) {
+                let doc_end = self.current;
+                title.bytes().for_each(|_| self.advance());
+                return Ok(self.make_token_from(TokenType::HereDoc, doc_start, doc_end));
+            } else if self.is_at_end() {
+                return self.error("Unterminated heredoc");
             } else {
-                doc.push(self.next_char());
+                self.advance();
             }
         }
-
-        self.make_token(TokenType::HereDoc, doc)
     }
 }
 
+// PHP allows any byte from 0x80 up in names, which covers every non-ASCII UTF-8 byte
+fn is_identifier_char(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c >= 0x80
+}
+
 impl<'a> Iterator for Tokenizer<'a> {
-    type Item = Token;
+    type Item = Result<Token<'a>, String>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.scan_token()
@@ -169,145 +191,138 @@ impl<'a> Iterator for Tokenizer<'a> {
 }
 
 impl<'a> Tokenizer<'a> {
-    fn scan_token(&mut self) -> Option<Token> {
-        let char = match self.next_char_opt() {
-            Some(char) => char,
-            None => return None,
-        };
+    fn scan_token(&mut self) -> Option<Result<Token<'a>, String>> {
+        self.start = self.current;
+        let char = self.next_char_opt()?;
         let token = match char {
-            ' ' | '\r' | '\t' => return self.scan_token(),
-            '\n' => return self.scan_token(),
-            '{' => self.make_token(TokenType::LeftBrace, "{".to_string()),
-            '}' => self.make_token(TokenType::RightBrace, "}".to_string()),
-            '(' => self.make_token(TokenType::LeftParen, "(".to_string()),
-            ')' => self.make_token(TokenType::RightParen, ")".to_string()),
-            '[' => self.make_token(TokenType::LeftBracket, "[".to_string()),
-            ']' => self.make_token(TokenType::RightBracket, "]".to_string()),
-            ',' => self.make_token(TokenType::Comma, ",".to_string()),
-            '.' => self.make_token(TokenType::Dot, ".".to_string()),
-            '-' => {
-                if self.peek().is_some_and(|c| c == &'>') {
+            b' ' | b'\r' | b'\t' => return self.scan_token(),
+            b'\n' => return self.scan_token(),
+            b'{' => self.make_token(TokenType::LeftBrace),
+            b'}' => self.make_token(TokenType::RightBrace),
+            b'(' => self.make_token(TokenType::LeftParen),
+            b')' => self.make_token(TokenType::RightParen),
+            b'[' => self.make_token(TokenType::LeftBracket),
+            b']' => self.make_token(TokenType::RightBracket),
+            b',' => self.make_token(TokenType::Comma),
+            b'.' => self.make_token(TokenType::Dot),
+            b'-' => {
+                if self.peek().is_some_and(|c| c == b'>') {
                     self.advance();
-                    self.make_token(TokenType::ThinArrow, "->".to_string())
+                    self.make_token(TokenType::ThinArrow)
                 } else {
-                    self.make_token(TokenType::Minus, "-".to_string())
+                    self.make_token(TokenType::Minus)
                 }
             }
-            '+' => self.make_token(TokenType::Plus, "+".to_string()),
-            ';' => self.make_token(TokenType::Semicolon, ";".to_string()),
-            '#' => self.make_token(TokenType::Hash, "#".to_string()),
-            '/' => {
-                if self.match_char('*') {
-                    while !(self.code.is_empty()
-                        || (self.peek().is_some_and(|c| c == &'*')
-                            && self.peek_next().is_some_and(|c| c == &'/')))
+            b'+' => self.make_token(TokenType::Plus),
+            b';' => self.make_token(TokenType::Semicolon),
+            b'#' => self.make_token(TokenType::Hash),
+            b'/' => {
+                if self.match_char(b'*') {
+                    while !(self.is_at_end()
+                        || (self.peek().is_some_and(|c| c == b'*')
+                            && self.peek_next().is_some_and(|c| c == b'/')))
                     {
                         self.advance();
                     }
-                    if self.code.len() < 2 {
-                        error("Unterminated block comment", self.line);
+                    if self.source.len() - self.current < 2 {
+                        return Some(self.error("Unterminated block comment"));
                     }
                     self.advance();
                     self.advance();
                     return self.scan_token();
-                } else if self.match_char('/') {
-                    while self.peek().is_some_and(|c| c != &'\n') {
+                } else if self.match_char(b'/') {
+                    while self.peek().is_some_and(|c| c != b'\n') {
                         self.advance();
                     }
                     self.advance();
                     return self.scan_token();
                 }
-                self.make_token(TokenType::Slash, "/".to_string())
+                self.make_token(TokenType::Slash)
             }
-            '*' => self.make_token(TokenType::Star, "*".to_string()),
-            '?' => self.make_token(TokenType::Question, "?".to_string()),
-            ':' => {
-                if self.peek().is_some_and(|c| c == &':') {
+            b'*' => self.make_token(TokenType::Star),
+            b'?' => self.make_token(TokenType::Question),
+            b':' => {
+                if self.peek().is_some_and(|c| c == b':') {
                     self.advance();
-                    self.make_token(TokenType::ColonColon, "::".to_string())
+                    self.make_token(TokenType::ColonColon)
                 } else {
-                    self.make_token(TokenType::Colon, ":".to_string())
+                    self.make_token(TokenType::Colon)
                 }
             }
-            '!' => {
-                if self.match_char('=') {
-                    if self.match_char('=') {
-                        self.make_token(TokenType::BangEqualEqual, "!==".to_string())
+            b'!' => {
+                if self.match_char(b'=') {
+                    if self.match_char(b'=') {
+                        self.make_token(TokenType::BangEqualEqual)
                     } else {
-                        self.make_token(TokenType::BangEqual, "!=".to_string())
+                        self.make_token(TokenType::BangEqual)
                     }
                 } else {
-                    self.make_token(TokenType::Bang, "!".to_string())
+                    self.make_token(TokenType::Bang)
                 }
             }
-            '=' => {
-                if self.match_char('=') {
-                    if self.match_char('=') {
-                        self.make_token(TokenType::EqualEqualEqual, "===".to_string())
+            b'=' => {
+                if self.match_char(b'=') {
+                    if self.match_char(b'=') {
+                        self.make_token(TokenType::EqualEqualEqual)
                     } else {
-                        self.make_token(TokenType::EqualEqual, "==".to_string())
+                        self.make_token(TokenType::EqualEqual)
                     }
-                } else if self.match_char('>') {
-                    self.make_token(TokenType::FatArrow, "=>".to_string())
+                } else if self.match_char(b'>') {
+                    self.make_token(TokenType::FatArrow)
                 } else {
-                    self.make_token(TokenType::Equal, "=".to_string())
+                    self.make_token(TokenType::Equal)
                 }
             }
-            '>' => {
-                if self.match_char('=') {
-                    self.make_token(TokenType::GreaterEqual, ">=".to_string())
+            b'>' => {
+                if self.match_char(b'=') {
+                    self.make_token(TokenType::GreaterEqual)
                 } else {
-                    self.make_token(TokenType::Greater, ">".to_string())
+                    self.make_token(TokenType::Greater)
                 }
             }
-            '<' => {
-                if self.match_char('<') && self.match_char('<') {
-                    self.here_doc()
-                } else if self.match_char('?')
-                    && self.match_char('p')
-                    && self.match_char('h')
-                    && self.match_char('p')
+            b'<' => {
+                if self.match_char(b'<') && self.match_char(b'<') {
+                    return Some(self.here_doc());
+                } else if self.match_char(b'?')
+                    && self.match_char(b'p')
+                    && self.match_char(b'h')
+                    && self.match_char(b'p')
                 {
-                    self.make_token(TokenType::PhpTag, "<?php".to_string())
-                } else if self.match_char('=') {
-                    self.make_token(TokenType::LessEqual, "<=".to_string())
+                    self.make_token(TokenType::PhpTag)
+                } else if self.match_char(b'=') {
+                    self.make_token(TokenType::LessEqual)
                 } else {
-                    self.make_token(TokenType::Less, "<".to_string())
+                    self.make_token(TokenType::Less)
                 }
             }
-            '|' => {
-                if self.match_char('|') {
-                    self.make_token(TokenType::OrOperator, "||".to_string())
+            b'|' => {
+                if self.match_char(b'|') {
+                    self.make_token(TokenType::OrOperator)
                 } else {
-                    self.make_token(TokenType::Pipe, "|".to_string())
+                    self.make_token(TokenType::Pipe)
                 }
             }
-            '&' => {
-                if self.match_char('&') {
-                    self.make_token(TokenType::AndOperator, "&&".to_string())
+            b'&' => {
+                if self.match_char(b'&') {
+                    self.make_token(TokenType::AndOperator)
                 } else {
-                    self.make_token(TokenType::Reference, "&".to_string())
+                    self.make_token(TokenType::Reference)
                 }
             }
-            '~' => self.make_token(TokenType::BinaryNegation, "~".to_string()),
-            '%' => self.make_token(TokenType::Modulo, "%".to_string()),
-            '"' => self.string('"'),
-            '\'' => self.string('\''),
-            '0'..='9' => self.number(),
-            '@' => self.make_token(TokenType::AtSign, "@".to_string()),
-            '_' | 'a'..='z' | 'A'..='Z' | '$' | '\\' => self.identifier(char),
+            b'~' => self.make_token(TokenType::BinaryNegation),
+            b'%' => self.make_token(TokenType::Modulo),
+            b'"' => return Some(self.string(b'"')),
+            b'\'' => return Some(self.string(b'\'')),
+            b'0'..=b'9' => self.number(),
+            b'@' => self.make_token(TokenType::AtSign),
+            b'_' | b'a'..=b'z' | b'A'..=b'Z' | b'$' | b'\\' | 0x80.. => self.identifier(),
             _ => {
-                println!(
-                    "{char} line {}, peek next 2: {}{}",
-                    self.line,
-                    self.peek().unwrap(),
-                    self.peek_next().unwrap()
-                );
-                todo!();
+                let message = format!("Unexpected character {:?}", char as char);
+                return Some(self.error(&message));
             }
         };
 
-        Some(token)
+        Some(Ok(token))
     }
 }
 
@@ -320,23 +335,71 @@ mod tests {
         let code = " \
             'string';
             \"String with a 'string' inside\";
-        "
-        .chars()
-        .collect::<Vec<_>>();
-        let tokens: Vec<Token> = Tokenizer::new(&code).collect();
+        ";
+        let tokens: Vec<Token> = Tokenizer::new(code).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(
+            vec![
+                Token::new(TokenType::String, 1, "string"),
+                Token::new(TokenType::Semicolon, 1, ";"),
+                Token::new(TokenType::String, 2, "String with a 'string' inside"),
+                Token::new(TokenType::Semicolon, 2, ";"),
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_here_doc_and_identifiers() {
+        let code = "$café = <<<EOT\nline one\nEOT;\nFoo\\Bar::class;";
+        let tokens: Vec<Token> = Tokenizer::new(code).collect::<Result<_, _>>().unwrap();
 
         assert_eq!(
             vec![
-                Token::new(TokenType::String, 1, "string".to_string()),
-                Token::new(TokenType::Semicolon, 1, ";".to_string()),
-                Token::new(
-                    TokenType::String,
-                    2,
-                    "String with a 'string' inside".to_string()
-                ),
-                Token::new(TokenType::Semicolon, 2, ";".to_string()),
+                Token::new(TokenType::Identifier, 1, "$café"),
+                Token::new(TokenType::Equal, 1, "="),
+                Token::new(TokenType::HereDoc, 3, "line one\n"),
+                Token::new(TokenType::Semicolon, 3, ";"),
+                Token::new(TokenType::Identifier, 4, "Foo\\Bar"),
+                Token::new(TokenType::ColonColon, 4, "::"),
+                Token::new(TokenType::Identifier, 4, "class"),
+                Token::new(TokenType::Semicolon, 4, ";"),
             ],
             tokens
         );
     }
+
+    #[test]
+    fn test_non_ascii_here_doc() {
+        let code = "$a = <<<EOT\ncafé crème\nEOT;";
+        let tokens: Vec<Token> = Tokenizer::new(code).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(
+            vec![
+                Token::new(TokenType::Identifier, 1, "$a"),
+                Token::new(TokenType::Equal, 1, "="),
+                Token::new(TokenType::HereDoc, 3, "café crème\n"),
+                Token::new(TokenType::Semicolon, 3, ";"),
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_malformed_source() {
+        let error = |code| Tokenizer::new(code).find_map(Result::err);
+        assert_eq!(
+            Some("line 2: Unterminated string".to_string()),
+            error("$a;\n$b = 'open;")
+        );
+        assert_eq!(
+            Some("line 1: Unexpected character '`'".to_string()),
+            error("$a = `ls`;")
+        );
+        assert!(error("$a = <<<EOT\nnever closed").is_some());
+        assert_eq!(
+            Some("line 1: Expected a new line after the heredoc title".to_string()),
+            error("$a = <<<\"EOT\"é\nEOT;")
+        );
+    }
 }
@@ -0,0 +1,198 @@
+use std::{
+    fs,
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::UNIX_EPOCH,
+};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::{overrides::OverrideBuilder, WalkBuilder};
+
+use crate::indexing::{IndexOptions, SourceFile};
+
+/// Walks the scanned directory in name order, honouring .gitignore, .tcplscanignore and
+/// the include and exclude globs. Returns whether the vendor directory was skipped.
+pub fn walk(path: &str, options: &IndexOptions, on_file: &mut dyn FnMut(SourceFile)) -> bool {
+    let include = include_set(&options.include);
+    let mut overrides = OverrideBuilder::new(path);
+    for glob in options.exclude.iter() {
+        add_glob(&mut overrides, &format!("!{glob}"), glob);
+    }
+    let overrides = overrides.build().unwrap_or_else(|err| {
+        eprintln!("ERROR: Failed to build exclude patterns, {err}");
+        process::exit(1);
+    });
+
+    let vendor_dir: Option<PathBuf> = match options.composer {
+        Some(composer) if !options.include_vendor => Some(composer.vendor_dir().to_path_buf()),
+        _ => None,
+    };
+    let vendor_skipped = Arc::new(AtomicBool::new(false));
+    let skipped = vendor_skipped.clone();
+
+    let mut roots = options.paths.iter();
+    let mut walker = match roots.next() {
+        Some(root) => WalkBuilder::new(root),
+        None => WalkBuilder::new(path),
+    };
+    for root in roots {
+        walker.add(root);
+    }
+    let walker = walker
+        .require_git(false)
+        .add_custom_ignore_filename(".tcplscanignore")
+        .overrides(overrides)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            let is_vendor = vendor_dir.as_ref().is_some_and(|vendor_dir| {
+                entry.file_type().is_some_and(|t| t.is_dir())
+                    && fs::canonicalize(entry.path()).is_ok_and(|path| &path == vendor_dir)
+            });
+            if is_vendor {
+                skipped.store(true, Ordering::Relaxed);
+            }
+            !is_vendor
+        })
+        .build();
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                eprintln!("ERROR: Failed to read directory entry, {err}");
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let has_extension = entry.path().extension().is_some_and(|extension| {
+            options
+                .extensions
+                .iter()
+                .any(|allowed| extension == allowed.as_str())
+        });
+        if !has_extension {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+        if include
+            .as_ref()
+            .is_some_and(|include| !include.is_match(relative))
+        {
+            continue;
+        }
+        // The file may be gone by now, editors often save by renaming a temporary file
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                eprintln!("ERROR: Failed to read metadata of directory entry, {err}");
+                continue;
+            }
+        };
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |modified| modified.as_nanos() as u64);
+        on_file(SourceFile {
+            path: entry.into_path().into_os_string().into_string().unwrap(),
+            modified,
+            size: metadata.len(),
+        });
+    }
+
+    vendor_skipped.load(Ordering::Relaxed)
+}
+
+/// The include globs, None when there are none. They are matched after the walk rather
+/// than added to the overrides, where they would bring back ignored files. Like in
+/// .gitignore a glob without a slash matches the file name in any directory.
+fn include_set(globs: &[String]) -> Option<GlobSet> {
+    if globs.is_empty() {
+        return None;
+    }
+    let mut set = GlobSetBuilder::new();
+    for pattern in globs.iter() {
+        let glob = match pattern.trim_start_matches('/') {
+            glob if pattern.contains('/') => glob.to_string(),
+            glob => format!("**/{glob}"),
+        };
+        match GlobBuilder::new(&glob).literal_separator(true).build() {
+            Ok(glob) => set.add(glob),
+            Err(err) => {
+                eprintln!("ERROR: Invalid pattern {pattern}, {err}");
+                process::exit(1);
+            }
+        };
+    }
+    Some(set.build().unwrap_or_else(|err| {
+        eprintln!("ERROR: Failed to build include patterns, {err}");
+        process::exit(1);
+    }))
+}
+
+fn add_glob(overrides: &mut OverrideBuilder, glob: &str, pattern: &str) {
+    if let Err(err) = overrides.add(glob) {
+        eprintln!("ERROR: Invalid pattern {pattern}, {err}");
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::Path};
+
+    use super::*;
+
+    #[test]
+    fn test_include_honours_ignore_files() {
+        let dir = env::temp_dir().join(format!("tcplscan-walk-{}", process::id()));
+        for file in [
+            "src/Invoice.php",
+            "src/Generated/Proxy.php",
+            "src/Billing/Tax.php",
+            "lib/Helper.php",
+        ] {
+            fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+            fs::write(dir.join(file), "<?php\n").unwrap();
+        }
+        fs::write(dir.join(".gitignore"), "src/Generated/\n").unwrap();
+        let path = dir.to_str().unwrap();
+        let scanned = |include: &[&str], exclude: &[&str]| {
+            let options = IndexOptions {
+                composer: None,
+                include_vendor: false,
+                paths: Vec::new(),
+                include: include.iter().map(|glob| glob.to_string()).collect(),
+                exclude: exclude.iter().map(|glob| glob.to_string()).collect(),
+                extensions: vec!["php".to_string()],
+                threads: 1,
+                cache_path: None,
+                clear_cache: false,
+                histories: false,
+            };
+            let mut files = Vec::new();
+            walk(path, &options, &mut |source| {
+                let relative = Path::new(&source.path).strip_prefix(&dir).unwrap();
+                files.push(relative.to_str().unwrap().to_string());
+            });
+            files
+        };
+
+        assert_eq!(
+            vec!["src/Billing/Tax.php", "src/Invoice.php"],
+            scanned(&["src/**"], &[])
+        );
+        assert_eq!(vec!["src/Invoice.php"], scanned(&["Invoice.php"], &[]));
+        assert_eq!(
+            vec!["lib/Helper.php", "src/Invoice.php"],
+            scanned(&[], &["src/Billing"])
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::indexing::{
+    add_to_index, parser::Class, read_and_parse, remove_from_index, walk::walk,
+    ClassDependencyIndex, File, IndexOptions, SourceFile,
+};
+
+/// A PHP file that was added, changed or deleted since the last poll.
+#[derive(Debug)]
+pub struct Change {
+    pub path: String,
+    pub before: Option<Class>,
+    pub after: Option<Class>,
+}
+
+/// Keeps the files and index of a scan up to date by polling the directory for changes.
+pub struct Watcher<'a> {
+    path: String,
+    options: &'a IndexOptions<'a>,
+    // Modification time and size of every PHP file as of the last poll
+    known: HashMap<String, (u64, u64)>,
+}
+
+impl<'a> Watcher<'a> {
+    pub fn new(path: &str, options: &'a IndexOptions<'a>) -> Self {
+        let mut watcher = Self {
+            path: path.to_string(),
+            options,
+            known: HashMap::new(),
+        };
+        watcher.known = watcher
+            .snapshot()
+            .into_iter()
+            .map(|source| (source.path, (source.modified, source.size)))
+            .collect();
+        watcher
+    }
+
+    fn snapshot(&self) -> Vec<SourceFile> {
+        let mut sources = Vec::new();
+        walk(&self.path, self.options, &mut |source| sources.push(source));
+        sources
+    }
+
+    /// Re-parses only the files that changed since the last poll and updates `files` and
+    /// `index` in place. A file that doesn't parse keeps its last good class and is
+    /// returned as an error instead of a change.
+    pub fn poll(
+        &mut self,
+        index: &mut ClassDependencyIndex,
+        files: &mut Vec<File>,
+    ) -> (Vec<Change>, Vec<String>) {
+        let mut changes = Vec::new();
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+        for source in self.snapshot() {
+            seen.insert(source.path.clone());
+            let state = (source.modified, source.size);
+            if self.known.get(&source.path) == Some(&state) {
+                continue;
+            }
+            self.known.insert(source.path.clone(), state);
+            let mut after = match read_and_parse(&source, self.options.composer) {
+                Ok(after) => after,
+                Err(err) => {
+                    errors.push(err);
+                    continue;
+                }
+            };
+            let before = take_file(files, &source.path);
+            // Saving a file doesn't change its commits, so keep the history read at startup
+            if let (Some(after), Some(before)) = (&mut after, &before) {
+                after.history = before.history.clone();
+            }
+            changes.push(update(index, files, source.path, before, after));
+        }
+
+        let deleted = self
+            .known
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect::<Vec<_>>();
+        for path in deleted {
+            self.known.remove(&path);
+            let before = take_file(files, &path);
+            changes.push(update(index, files, path, before, None));
+        }
+
+        // Another file may still declare a class we just took out of the index
+        for file in files.iter() {
+            index.entry(file.class.name.to_owned()).or_insert(0);
+        }
+        changes.retain(|change| change.before.is_some() || change.after.is_some());
+        (changes, errors)
+    }
+}
+
+fn take_file(files: &mut Vec<File>, path: &str) -> Option<File> {
+    let i = files.iter().position(|file| file.path == path)?;
+    Some(files.remove(i))
+}
+
+fn update(
+    index: &mut ClassDependencyIndex,
+    files: &mut Vec<File>,
+    path: String,
+    before: Option<File>,
+    after: Option<File>,
+) -> Change {
+    if let Some(before) = &before {
+        remove_from_index(index, &before.class);
+    }
+    let after_class = after.as_ref().map(|file| file.class.clone());
+    if let Some(after) = after {
+        add_to_index(index, &after.class);
+        files.push(after);
+    }
+    Change {
+        path,
+        before: before.map(|file| file.class),
+        after: after_class,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+    use crate::indexing::History;
+
+    #[test]
+    fn test_poll() {
+        let dir = env::temp_dir().join(format!("tcplscan-watch-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Invoice.php");
+        let options = IndexOptions {
+            composer: None,
+            include_vendor: false,
+            paths: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            extensions: vec!["php".to_string()],
+            threads: 1,
+            cache_path: None,
+            clear_cache: false,
+            histories: false,
+        };
+        let mut watcher = Watcher::new(dir.to_str().unwrap(), &options);
+        let mut index = ClassDependencyIndex::new();
+        let mut files = Vec::new();
+        let names = |change: &Change| {
+            let name = |class: &Option<Class>| class.as_ref().map(|class| class.name.clone());
+            (name(&change.before), name(&change.after))
+        };
+
+        fs::write(&path, "<?php\nnamespace App;\nclass Invoice\n{\n}\n").unwrap();
+        let (changes, errors) = watcher.poll(&mut index, &mut files);
+        assert!(errors.is_empty());
+        assert_eq!(
+            vec![(None, Some("App\\Invoice".to_string()))],
+            changes.iter().map(names).collect::<Vec<_>>()
+        );
+        let history = History {
+            commits: 3,
+            authors: 2,
+            last_commit: 1,
+        };
+        files[0].history = Some(history.clone());
+
+        fs::write(
+            &path,
+            "<?php\nnamespace App;\nclass Invoice extends Model\n{\n}\n",
+        )
+        .unwrap();
+        let (changes, _) = watcher.poll(&mut index, &mut files);
+        assert_eq!(1, changes.len());
+        assert_eq!(Some("App\\Model".to_string()), files[0].class.extends);
+        assert_eq!(Some(history), files[0].history);
+
+        fs::write(
+            &path,
+            "<?php\nnamespace App;\nclass Invoice\n{\n    $a = 'open;\n",
+        )
+        .unwrap();
+        let (changes, errors) = watcher.poll(&mut index, &mut files);
+        assert!(changes.is_empty());
+        assert_eq!(1, errors.len());
+        assert_eq!(Some("App\\Model".to_string()), files[0].class.extends);
+
+        fs::remove_file(&path).unwrap();
+        let (changes, _) = watcher.poll(&mut index, &mut files);
+        assert_eq!(
+            vec![(Some("App\\Invoice".to_string()), None)],
+            changes.iter().map(names).collect::<Vec<_>>()
+        );
+        assert!(files.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
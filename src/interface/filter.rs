@@ -0,0 +1,273 @@
+use crate::{ClassDependencyIndex, File};
+
+use super::SortOrder;
+
+/// A parsed filter expression such as
+/// `namespace:App\Billing complexity>8 deps>=10 abstract:false extends:BaseController`.
+/// Terms are separated by whitespace and must all match, a leading `-` negates a term
+/// and a bare word matches anywhere in the class name.
+#[derive(Debug, Default)]
+pub struct Filter {
+    terms: Vec<Term>,
+    /// Set by a `sort:` term
+    pub sort: Option<SortOrder>,
+}
+
+#[derive(Debug)]
+struct Term {
+    negated: bool,
+    condition: Condition,
+}
+
+#[derive(Debug, PartialEq)]
+enum Condition {
+    Name(String),
+    Namespace(String),
+    Extends(String),
+    Implements(String),
+    DependsOn(String),
+    Abstract(bool),
+    Metric(Metric, Comparison, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    Complexity,
+    MaxComplexity,
+    Dependencies,
+    Uses,
+    Lines,
+    Functions,
+    Commits,
+    Authors,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let mut filter = Filter::default();
+        for term in expression.split_whitespace() {
+            let (negated, term) = match term.strip_prefix('-') {
+                Some(term) if !term.is_empty() => (true, term),
+                _ => (false, term),
+            };
+            let Some(at) = term.find([':', '=', '<', '>']) else {
+                filter.terms.push(Term {
+                    negated,
+                    condition: Condition::Name(term.to_lowercase()),
+                });
+                continue;
+            };
+            let key = term[..at].to_lowercase();
+            let rest = &term[at..];
+            let (comparison, value) = if let Some(value) = rest.strip_prefix(">=") {
+                (Comparison::GreaterOrEqual, value)
+            } else if let Some(value) = rest.strip_prefix("<=") {
+                (Comparison::LessOrEqual, value)
+            } else if let Some(value) = rest.strip_prefix('>') {
+                (Comparison::Greater, value)
+            } else if let Some(value) = rest.strip_prefix('<') {
+                (Comparison::Less, value)
+            } else {
+                (Comparison::Equal, &rest[1..])
+            };
+            if value.is_empty() {
+                return Err(format!("Missing value for {key} in {term}"));
+            }
+
+            if key == "sort" {
+                filter.sort = Some(SortOrder::parse(value)?);
+                continue;
+            }
+
+            let metric = match key.as_str() {
+                "complexity" | "avg" => Some(Metric::Complexity),
+                "max" => Some(Metric::MaxComplexity),
+                "deps" | "dependencies" => Some(Metric::Dependencies),
+                "uses" => Some(Metric::Uses),
+                "lines" => Some(Metric::Lines),
+                "functions" | "methods" => Some(Metric::Functions),
+                "commits" => Some(Metric::Commits),
+                "authors" => Some(Metric::Authors),
+                _ => None,
+            };
+            let condition = if let Some(metric) = metric {
+                let number = value
+                    .parse::<f64>()
+                    .map_err(|_| format!("{key} expects a number, got {value}"))?;
+                Condition::Metric(metric, comparison, number)
+            } else {
+                if comparison != Comparison::Equal {
+                    return Err(format!("{key} can't be compared with a number"));
+                }
+                let name = value.replace("\\\\", "\\");
+                let name = name.trim_matches('\\').to_lowercase();
+                match key.as_str() {
+                    "name" => Condition::Name(name),
+                    "namespace" | "ns" => Condition::Namespace(name),
+                    "extends" => Condition::Extends(name),
+                    "implements" => Condition::Implements(name),
+                    "depends" => Condition::DependsOn(name),
+                    "abstract" => match name.as_str() {
+                        "true" | "yes" => Condition::Abstract(true),
+                        "false" | "no" => Condition::Abstract(false),
+                        _ => return Err(format!("abstract expects true or false, got {value}")),
+                    },
+                    _ => return Err(format!("Unknown filter key {key}")),
+                }
+            };
+            filter.terms.push(Term { negated, condition });
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, file: &File, index: &ClassDependencyIndex) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.condition.matches(file, index) != term.negated)
+    }
+}
+
+impl Condition {
+    fn matches(&self, file: &File, index: &ClassDependencyIndex) -> bool {
+        let class = &file.class;
+        match self {
+            Condition::Name(name) => class.name.to_lowercase().contains(name),
+            Condition::Namespace(namespace) => {
+                let name = class.name.trim_start_matches('\\').to_lowercase();
+                let own = name
+                    .rsplit_once('\\')
+                    .map_or("", |(namespace, _)| namespace);
+                own == namespace || own.starts_with(&format!("{namespace}\\"))
+            }
+            Condition::Extends(parent) => class
+                .extends
+                .as_ref()
+                .is_some_and(|extends| same_class(extends, parent)),
+            Condition::Implements(interface) => class
+                .implements
+                .iter()
+                .any(|implements| same_class(implements, interface)),
+            Condition::DependsOn(dependency) => class
+                .dependencies
+                .iter()
+                .any(|name| same_class(name, dependency)),
+            Condition::Abstract(is_abstract) => class.is_abstract == *is_abstract,
+            Condition::Metric(metric, comparison, limit) => {
+                let value = match metric {
+                    Metric::Complexity => class.average_complexity(),
+                    Metric::MaxComplexity => class.highest_complexity_function() as f64,
+                    Metric::Dependencies => class.dependencies.len() as f64,
+                    Metric::Uses => index.get(&class.name).copied().unwrap_or(0) as f64,
+                    Metric::Lines => file.lines as f64,
+                    Metric::Functions => class.functions.len() as f64,
+                    Metric::Commits => file.history.as_ref().map_or(0, |h| h.commits) as f64,
+                    Metric::Authors => file.history.as_ref().map_or(0, |h| h.authors) as f64,
+                };
+                match comparison {
+                    Comparison::Equal => value == *limit,
+                    Comparison::Greater => value > *limit,
+                    Comparison::GreaterOrEqual => value >= *limit,
+                    Comparison::Less => value < *limit,
+                    Comparison::LessOrEqual => value <= *limit,
+                }
+            }
+        }
+    }
+}
+
+/// Whether `name` is the class `pattern`, given either fully qualified or by its short name.
+fn same_class(name: &str, pattern: &str) -> bool {
+    let name = name.trim_start_matches('\\').to_lowercase();
+    name == pattern || name.ends_with(&format!("\\{pattern}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let filter = Filter::parse(
+            "namespace:App\\\\Billing complexity>8 deps>=10 -abstract:false extends:BaseController",
+        )
+        .unwrap();
+        let conditions = filter
+            .terms
+            .iter()
+            .map(|term| &term.condition)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                &Condition::Namespace("app\\billing".to_string()),
+                &Condition::Metric(Metric::Complexity, Comparison::Greater, 8.0),
+                &Condition::Metric(Metric::Dependencies, Comparison::GreaterOrEqual, 10.0),
+                &Condition::Abstract(false),
+                &Condition::Extends("basecontroller".to_string()),
+            ],
+            conditions
+        );
+        assert!(filter.terms[3].negated);
+        assert!(Filter::parse("color:red").is_err());
+        assert!(Filter::parse("deps>many").is_err());
+        assert!(same_class("App\\Http\\BaseController", "basecontroller"));
+        assert!(!same_class("App\\Http\\MyBaseController", "basecontroller"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut invoice = File::test(
+            "App\\Billing\\Invoice",
+            &["App\\Billing\\Money", "Money\\Currency"],
+        );
+        invoice.class.extends = Some("App\\Model".to_string());
+        invoice.lines = 200;
+        let mut base = File::test("App\\Model", &[]);
+        base.class.is_abstract = true;
+        let report = File::test("App\\Reports\\InvoiceReport", &["App\\Billing\\Invoice"]);
+        let files = [invoice, base, report];
+        let index = ClassDependencyIndex::from([
+            ("App\\Billing\\Invoice".to_string(), 3),
+            ("App\\Model".to_string(), 1),
+        ]);
+        let matching = |expression: &str| {
+            let filter = Filter::parse(expression).unwrap();
+            files
+                .iter()
+                .filter(|file| filter.matches(file, &index))
+                .map(|file| file.class.name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec!["App\\Billing\\Invoice", "App\\Reports\\InvoiceReport"],
+            matching("invoice")
+        );
+        assert_eq!(vec!["App\\Billing\\Invoice"], matching("uses>=2 lines>100"));
+        assert_eq!(
+            vec!["App\\Model", "App\\Reports\\InvoiceReport"],
+            matching("deps<2")
+        );
+        assert_eq!(vec!["App\\Model"], matching("uses=1"));
+        assert_eq!(
+            vec!["App\\Billing\\Invoice", "App\\Reports\\InvoiceReport"],
+            matching("-abstract:true")
+        );
+        assert_eq!(
+            vec!["App\\Reports\\InvoiceReport"],
+            matching("invoice -ns:App\\Billing")
+        );
+        assert_eq!(
+            vec!["App\\Billing\\Invoice"],
+            matching("extends:Model depends:Currency")
+        );
+    }
+}
@@ -1,15 +1,44 @@
 use colored::Colorize;
-use std::{fmt::Display, process, time::SystemTime};
+use serde::Deserialize;
+use std::{
+    fmt::Display,
+    io::IsTerminal,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::{ClassDependencyIndex, File};
+use crate::{
+    config::ViewConfig,
+    indexing::Function,
+    report::{self, FunctionMetric, Namespace, Profile, RankedFunction, ScanDiff, Violation},
+    ClassDependencyIndex, Composer, File,
+};
 
+pub use self::filter::Filter;
+pub use self::sort::{sort_files, SortOrder};
+pub use self::watch::run_watch;
+
+use self::tui::run_tui;
+
+mod filter;
 mod io;
+mod sort;
+mod tui;
+mod watch;
 
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum SortType {
     ClassComplexity,
     Uses,
     Dependencies,
     FunctionComplexity,
+    LastModified,
+    Commits,
+    Authors,
+    Hotspot,
+    Lines,
+    Functions,
 }
 
 pub struct ViewOptions {
@@ -17,7 +46,7 @@ pub struct ViewOptions {
     top_files: usize,
     num_functions: Option<usize>,
     function_stmts: bool,
-    query: Option<String>,
+    query: Option<Filter>,
 }
 
 impl ViewOptions {
@@ -30,6 +59,17 @@ impl ViewOptions {
             query: None,
         }
     }
+
+    pub fn from_config(config: &ViewConfig) -> Self {
+        let default = Self::default();
+        Self {
+            dependencies: config.dependencies.unwrap_or(default.dependencies),
+            top_files: config.top_files.unwrap_or(default.top_files),
+            num_functions: config.functions.or(default.num_functions),
+            function_stmts: config.function_statements.unwrap_or(default.function_stmts),
+            query: None,
+        }
+    }
 }
 
 impl Display for SortType {
@@ -42,25 +82,43 @@ impl Display for SortType {
                 SortType::Uses => "uses",
                 SortType::ClassComplexity => "class complexity",
                 SortType::FunctionComplexity => "function complexity",
+                SortType::LastModified => "last modified",
+                SortType::Commits => "commits",
+                SortType::Authors => "authors",
+                SortType::Hotspot => "hotspot score",
+                SortType::Lines => "lines",
+                SortType::Functions => "functions",
             }
         )
     }
 }
 
-pub fn run_program(index: &ClassDependencyIndex, files: &mut [File]) {
-    let sort_type = SortType::ClassComplexity;
+pub fn run_program(
+    index: &ClassDependencyIndex,
+    files: &mut [File],
+    composer: Option<&Composer>,
+    sort_order: SortOrder,
+    mut view_options: ViewOptions,
+    tui: bool,
+) {
     println!();
-    println!("Sorting by: {sort_type}");
+    println!("Sorting by: {sort_order}");
     let now = SystemTime::now();
     let diff = now.elapsed().unwrap().as_millis() as f64;
-    sort_files(files, sort_type, index);
+    sort_files(files, &sort_order, index);
     println!(
         "Sorted {} files in {:.4} seconds.",
         files.len(),
         diff / 1000.0
     );
 
-    let mut view_options = ViewOptions::default();
+    if tui && std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        if let Err(err) = run_tui(files, index, composer, sort_order) {
+            eprintln!("ERROR: Terminal UI failed, {err}");
+            process::exit(1);
+        }
+        exit();
+    }
 
     io::display_title("TCPL Scanner");
 
@@ -74,7 +132,12 @@ pub fn run_program(index: &ClassDependencyIndex, files: &mut [File]) {
         println!("3. Update view options");
         println!("4. Search for a file");
         println!("5. Re-sort files");
-        println!("8. Exit\n");
+        println!("6. View package coupling");
+        println!("7. Browse namespaces");
+        println!("8. View hotspots");
+        println!("9. View top functions");
+        println!("10. Look up a class");
+        println!("11. Exit\n");
 
         let option = match io::get_usize_input("Enter an option") {
             Ok(option) => option,
@@ -86,12 +149,17 @@ pub fn run_program(index: &ClassDependencyIndex, files: &mut [File]) {
         println!();
 
         match option {
-            1 => display_files(files, index, &view_options),
+            1 => display_files(files, index, composer, &view_options),
             2 => display_view_options(&view_options),
             3 => update_view_options(&mut view_options),
-            4 => search(files, index, &mut view_options),
+            4 => search(files, index, composer, &mut view_options),
             5 => re_sort(files, index),
-            8 => exit(),
+            6 => display_package_coupling(files, composer),
+            7 => browse_namespaces(files),
+            8 => display_hotspots(files, view_options.top_files),
+            9 => top_functions(files, &view_options),
+            10 => look_up(files, index, composer),
+            11 => exit(),
             _ => io::display_error("That's not right, try again!"),
         }
     }
@@ -194,38 +262,73 @@ fn display_view_options(view_options: &ViewOptions) {
     );
 }
 
-fn search(files: &[File], index: &ClassDependencyIndex, view_options: &mut ViewOptions) {
-    let query = match io::get_string_input("Enter query") {
+fn search(
+    files: &mut [File],
+    index: &ClassDependencyIndex,
+    composer: Option<&Composer>,
+    view_options: &mut ViewOptions,
+) {
+    let query = match io::get_string_input("Enter query, e.g. namespace:App\\Billing complexity>8")
+    {
         Ok(query) => query,
         Err(_) => return,
     };
-    view_options.query = Some(query);
-    display_files(files, index, view_options);
+    let filter = match Filter::parse(&query) {
+        Ok(filter) => filter,
+        Err(error) => {
+            io::display_error(&error);
+            return;
+        }
+    };
+    if let Some(sort_order) = &filter.sort {
+        sort_files(files, sort_order, index);
+    }
+    view_options.query = Some(filter);
+    display_files(files, index, composer, view_options);
     view_options.query = None;
 }
 
-pub fn display_files(files: &[File], index: &ClassDependencyIndex, view_options: &ViewOptions) {
+pub fn display_files(
+    files: &[File],
+    index: &ClassDependencyIndex,
+    composer: Option<&Composer>,
+    view_options: &ViewOptions,
+) {
     println!();
     io::display_title("Top Files");
     for (i, file) in files
         .iter()
         .filter(|file| {
-            if let Some(query) = &view_options.query {
-                file.class
-                    .name
-                    .to_lowercase()
-                    .contains(query.to_lowercase().as_str())
-            } else {
-                true
-            }
+            view_options
+                .query
+                .as_ref()
+                .is_none_or(|query| query.matches(file, index))
         })
         .take(view_options.top_files)
         .enumerate()
     {
         let class = &file.class;
         io::display_underlined_colored(format!("{}. {}", i + 1, class.name).as_str());
-        println!("Last accessed {} hours ago", file.last_accessed);
+        println!("Last modified {} hours ago", hours_since(file.modified));
+        if let Some(history) = &file.history {
+            println!(
+                "Commits: {} by {} authors, the last one {} days ago",
+                history.commits,
+                history.authors,
+                hours_since(history.last_commit) / 24
+            );
+        }
         println!("Path: {}", file.path);
+        if let Some(composer) = composer {
+            let package = composer.package_for(&class.name).unwrap_or("Unknown");
+            println!("Package: {package}");
+        }
+        if let Some(expected) = &file.autoload_mismatch {
+            println!(
+                "{}",
+                format!("Autoload mismatch, expected {expected}").red()
+            );
+        }
         println!("Lines: {}", file.lines);
         println!("Used in {} places", index.get(&class.name).unwrap());
         if class.dependencies.is_empty() {
@@ -234,7 +337,17 @@ pub fn display_files(files: &[File], index: &ClassDependencyIndex, view_options:
             println!("Dependencies: {}", class.dependencies.len());
             if view_options.dependencies {
                 println!("* ------ *");
-                io::display_list(&class.dependencies);
+                let dependencies = class
+                    .dependencies
+                    .iter()
+                    .map(|dependency| {
+                        match composer.and_then(|composer| composer.package_for(dependency)) {
+                            Some(package) => format!("{dependency} ({package})"),
+                            None => dependency.to_owned(),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                io::display_list(&dependencies);
             }
         }
         println!(
@@ -260,83 +373,370 @@ pub fn display_files(files: &[File], index: &ClassDependencyIndex, view_options:
             }
         }
         println!("Abstract: {}", class.is_abstract);
-        let functions = match view_options.num_functions {
-            Some(num) => {
-                if class.functions.len() >= num {
-                    &class.functions[..num]
-                } else {
-                    &class.functions
-                }
-            }
-            None => &class.functions,
-        };
+        let functions = class
+            .functions
+            .iter()
+            .filter(|function| file.is_changed(function))
+            .take(view_options.num_functions.unwrap_or(usize::MAX));
         for function in functions {
+            display_function(function, view_options.function_stmts);
+        }
+        println!("* ---------- *");
+    }
+}
+
+fn display_package_coupling(files: &[File], composer: Option<&Composer>) {
+    io::display_title("Package Coupling");
+    let packages = report::package_coupling(files, composer);
+    if packages.is_empty() {
+        println!("No third-party dependencies found");
+        return;
+    }
+    for (i, usage) in packages.iter().enumerate() {
+        io::display_underlined_colored(format!("{}. {}", i + 1, usage.package).as_str());
+        println!(
+            "Used by {} classes, {} references",
+            usage.classes.len(),
+            usage.references
+        );
+        println!("Package classes referenced: {}", usage.dependencies.len());
+        io::display_list(&usage.dependencies);
+        println!("Used by:");
+        let classes = usage
+            .classes
+            .iter()
+            .map(|(class, count)| format!("{class} ({count})"))
+            .collect::<Vec<_>>();
+        io::display_list(&classes);
+        println!();
+    }
+}
+
+fn display_hotspots(files: &[File], top: usize) {
+    io::display_title("Hotspots");
+    let hotspots = report::hotspots(files);
+    if hotspots.is_empty() {
+        println!(
+            "No git history found, hotspots need the scanned directory to be in a git repository"
+        );
+        return;
+    }
+    for (i, hotspot) in hotspots.iter().take(top).enumerate() {
+        io::display_underlined_colored(format!("{}. {}", i + 1, hotspot.class).as_str());
+        println!("Path: {}", hotspot.path);
+        println!("Score: {:.2}", hotspot.score);
+        println!("Commits: {}", hotspot.commits);
+        println!(
+            "Average cyclomatic complexity: {:.2}",
+            hotspot.average_complexity
+        );
+        println!("Max cyclomatic complexity: {}", hotspot.max_complexity);
+        println!();
+    }
+}
+
+pub fn display_functions(
+    functions: &[RankedFunction],
+    metric: FunctionMetric,
+    view_options: &ViewOptions,
+) {
+    io::display_title(format!("Top Functions by {metric}").as_str());
+    for (i, function) in functions.iter().take(view_options.top_files).enumerate() {
+        io::display_underlined_colored(
+            format!("{}. {}::{}", i + 1, function.class, function.function).as_str(),
+        );
+        println!("Path: {}:{}", function.path, function.line);
+        println!("Cyclomatic complexity: {}", function.complexity);
+        println!("Param count: {}", function.params);
+        println!("Length: {} lines", function.length);
+        println!("Nesting: {}", function.nesting);
+        println!();
+    }
+}
+
+fn top_functions(files: &[File], view_options: &ViewOptions) {
+    io::display_title("Rank Functions By");
+    println!("  1. Cyclomatic complexity");
+    println!("  2. Number of parameters");
+    println!("  3. Length in lines");
+    println!("  4. Nesting depth");
+
+    let metric = match io::get_usize_input("Choose a ranking") {
+        Ok(1) => FunctionMetric::Complexity,
+        Ok(2) => FunctionMetric::Params,
+        Ok(3) => FunctionMetric::Length,
+        Ok(4) => FunctionMetric::Nesting,
+        Ok(_) => {
+            io::display_error("Wrong input");
+            return;
+        }
+        Err(_) => return,
+    };
+    display_functions(&report::rank_functions(files, metric), metric, view_options);
+}
+
+fn browse_namespaces(files: &[File]) {
+    let root = report::namespace_tree(files);
+    let mut path: Vec<usize> = Vec::new();
+    loop {
+        let mut namespace = &root;
+        for i in path.iter() {
+            namespace = &namespace.children[*i];
+        }
+        let title = if namespace.name.is_empty() {
+            "Namespaces"
+        } else {
+            namespace.name.as_str()
+        };
+        io::display_title(title);
+        display_namespace_stats(namespace);
+        for (i, child) in namespace.children.iter().enumerate() {
             println!("* -------- *");
-            println!("  Name: {}", function.name);
-            println!("  Visibility: {}", function.visibility);
-            let return_type = if function.name == "__construct" {
-                "self".to_string()
-            } else {
-                match &function.return_type {
-                    Some(return_type) => return_type.clone(),
-                    None => "Not provided".to_string(),
-                }
-            };
-            println!("  Return type: {return_type}");
-            println!("  Param count: {}", function.params);
-            println!("  Cyclomatic complexity: {}", function.complexity());
-            if view_options.function_stmts {
-                for stmt in function.stmts.iter() {
-                    println!("  {:?}", stmt);
+            io::display_underlined_colored(format!("{}. {}", i + 1, child.name).as_str());
+            display_namespace_stats(child);
+        }
+        if !namespace.classes.is_empty() {
+            println!("* -------- *");
+            println!("Classes:");
+            io::display_list(&namespace.classes);
+        }
+        println!();
+
+        let input = match io::get_usize_input("Choose a namespace to drill into, or 0 to go back") {
+            Ok(input) => input,
+            Err(error) => {
+                io::display_error(&error);
+                continue;
+            }
+        };
+        match input {
+            0 => {
+                if path.pop().is_none() {
+                    break;
                 }
             }
+            i if i <= namespace.children.len() => path.push(i - 1),
+            _ => io::display_error("Wrong input"),
         }
-        println!("* ---------- *");
     }
 }
 
+fn display_namespace_stats(namespace: &Namespace) {
+    let stats = &namespace.stats;
+    println!("Classes: {}", stats.classes);
+    println!("Lines: {}", stats.lines);
+    println!("Total cyclomatic complexity: {}", stats.total_complexity);
+    println!(
+        "Average cyclomatic complexity: {:.2}",
+        stats.average_complexity()
+    );
+    println!(
+        "Max cyclomatic complexity: {}",
+        stats.max_function_complexity
+    );
+    println!(
+        "Dependencies: {} inbound, {} outbound",
+        stats.inbound, stats.outbound
+    );
+}
+
 fn re_sort(files: &mut [File], index: &ClassDependencyIndex) {
     io::display_title("Sort Options");
     println!("  1. Average cyclomatic complexity of a class");
     println!("  2. Usages of a class");
     println!("  3. Number of dependencies of a class");
     println!("  4. Maximum method complexity");
+    println!("  5. Most recently modified");
+    println!("  6. Number of commits");
+    println!("  7. Number of commit authors");
+    println!("  8. Hotspot score, commits times complexity");
+    println!("  9. Number of lines");
+    println!("  10. Number of functions");
+    println!("  11. Several keys, e.g. uses,lines:asc");
 
     let input = match io::get_usize_input("Choose a sorting option") {
         Ok(num) => num,
         Err(_) => return,
     };
 
-    match input {
-        1 => sort_files(files, SortType::ClassComplexity, index),
-        2 => sort_files(files, SortType::Uses, index),
-        3 => sort_files(files, SortType::Dependencies, index),
-        4 => sort_files(files, SortType::FunctionComplexity, index),
-        _ => io::display_error("Wrong input"),
+    let sort_type = match input {
+        1 => SortType::ClassComplexity,
+        2 => SortType::Uses,
+        3 => SortType::Dependencies,
+        4 => SortType::FunctionComplexity,
+        5 => SortType::LastModified,
+        6 => SortType::Commits,
+        7 => SortType::Authors,
+        8 => SortType::Hotspot,
+        9 => SortType::Lines,
+        10 => SortType::Functions,
+        11 => {
+            let spec = match io::get_string_input("Enter sort keys, add :asc for ascending") {
+                Ok(spec) => spec,
+                Err(_) => return,
+            };
+            match SortOrder::parse(&spec) {
+                Ok(sort_order) => sort_files(files, &sort_order, index),
+                Err(error) => io::display_error(&error),
+            }
+            return;
+        }
+        _ => {
+            io::display_error("Wrong input");
+            return;
+        }
+    };
+    sort_files(files, &SortOrder::from(sort_type), index);
+}
+
+fn display_function(function: &Function, stmts: bool) {
+    println!("* -------- *");
+    println!("  Name: {}", function.name);
+    println!("  Visibility: {}", function.visibility);
+    let return_type = if function.name == "__construct" {
+        "self".to_string()
+    } else {
+        match &function.return_type {
+            Some(return_type) => return_type.clone(),
+            None => "Not provided".to_string(),
+        }
+    };
+    println!("  Return type: {return_type}");
+    println!("  Param count: {}", function.params);
+    println!("  Cyclomatic complexity: {}", function.complexity());
+    println!("  Line: {}", function.line);
+    println!("  Length: {} lines", function.length);
+    println!("  Nesting: {}", function.nesting);
+    if stmts {
+        for stmt in function.stmts.iter() {
+            println!("  {stmt}");
+        }
     }
 }
 
-fn sort_files(files: &mut [File], sort_type: SortType, index: &ClassDependencyIndex) {
-    match sort_type {
-        SortType::ClassComplexity => {
-            files.sort_by(|a, b| {
-                b.class
-                    .average_complexity()
-                    .total_cmp(&a.class.average_complexity())
-            });
+/// Everything about one class, from `--show` or the menu's lookup.
+pub fn display_profile(profile: &Profile, composer: Option<&Composer>) {
+    let file = profile.file;
+    let class = &file.class;
+    io::display_title(&class.name);
+    println!("Path: {}:{}", file.path, class.line);
+    if let Some(composer) = composer {
+        let package = composer.package_for(&class.name).unwrap_or("Unknown");
+        println!("Package: {package}");
+    }
+    if let Some(expected) = &file.autoload_mismatch {
+        println!(
+            "{}",
+            format!("Autoload mismatch, expected {expected}").red()
+        );
+    }
+    println!("Abstract: {}", class.is_abstract);
+    println!("Lines: {}", file.lines);
+    println!("Last modified {} hours ago", hours_since(file.modified));
+    if let Some(history) = &file.history {
+        println!(
+            "Commits: {} by {} authors, the last one {} days ago",
+            history.commits,
+            history.authors,
+            hours_since(history.last_commit) / 24
+        );
+    }
+    println!("Used in {} places", profile.usages);
+    println!(
+        "Average cyclomatic complexity: {:.2}",
+        profile.average_complexity
+    );
+    println!("Max cyclomatic complexity: {}", profile.max_complexity);
+
+    let sections: [(&str, &[String]); 6] = [
+        ("Parents", &profile.parents),
+        ("Children", &profile.children),
+        ("Implements", &class.implements),
+        ("Traits", &class.traits),
+        ("Dependencies", &class.dependencies),
+        ("Dependents", &profile.dependents),
+    ];
+    for (title, names) in sections {
+        println!("* ------ *");
+        if names.is_empty() {
+            println!("{title}: None");
+        } else {
+            println!("{title}: {}", names.len());
+            io::display_list(names);
         }
-        SortType::Uses => {
-            files.sort_by(|a, b| index.get(&b.class.name).cmp(&index.get(&a.class.name)));
+    }
+
+    println!("* ------ *");
+    println!("Functions: {}", class.functions.len());
+    for function in class.functions.iter() {
+        display_function(function, true);
+    }
+    println!("* ---------- *");
+}
+
+fn look_up(files: &[File], index: &ClassDependencyIndex, composer: Option<&Composer>) {
+    let query = match io::get_string_input("Enter a fully qualified class name or a path") {
+        Ok(query) => query,
+        Err(_) => return,
+    };
+    match report::find_class(files, &query) {
+        Some(file) => display_profile(&report::profile(file, files, index), composer),
+        None => io::display_error(&format!("No class or file named {query}")),
+    }
+}
+
+fn hours_since(timestamp: u64) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    now.saturating_sub(timestamp) / 3600
+}
+
+pub fn display_violations(violations: &[Violation]) {
+    io::display_title("Violations");
+    if violations.is_empty() {
+        io::display_sucess("No violations found");
+        return;
+    }
+    for violation in violations.iter() {
+        println!("{} [{}]", violation.message.red(), violation.rule);
+        println!("  {}", violation.path);
+    }
+    println!();
+    println!("Found {} violations", violations.len());
+}
+
+pub fn display_diff(diff: &ScanDiff) {
+    io::display_title("Scan Diff");
+    println!("Added classes: {}", diff.added.len());
+    io::display_list(&diff.added);
+    println!("Removed classes: {}", diff.removed.len());
+    io::display_list(&diff.removed);
+    println!("Changed classes: {}", diff.changed.len());
+    for (i, change) in diff.changed.iter().enumerate() {
+        println!("* -------- *");
+        let regression = change.regression();
+        let title = format!("{}. {} ({regression:+.2})", i + 1, change.class);
+        if regression > 0.0 {
+            println!("{}", title.red().underline());
+        } else if regression < 0.0 {
+            println!("{}", title.green().underline());
+        } else {
+            io::display_underlined_colored(&title);
         }
-        SortType::Dependencies => {
-            files.sort_by(|a, b| b.class.dependencies.len().cmp(&a.class.dependencies.len()));
+        let (before, after) = change.average_complexity;
+        println!("Average cyclomatic complexity: {before:.2} -> {after:.2}");
+        let (before, after) = change.max_complexity;
+        println!("Max cyclomatic complexity: {before} -> {after}");
+        let (before, after) = change.uses;
+        println!("Used in {before} -> {after} places");
+        if !change.added_dependencies.is_empty() {
+            println!("Added dependencies:");
+            io::display_list(&change.added_dependencies);
         }
-        SortType::FunctionComplexity => {
-            files.sort_by(|a, b| {
-                b.class
-                    .highest_complexity_function()
-                    .cmp(&a.class.highest_complexity_function())
-            });
+        if !change.removed_dependencies.is_empty() {
+            println!("Removed dependencies:");
+            io::display_list(&change.removed_dependencies);
         }
     }
 }
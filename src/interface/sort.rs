@@ -0,0 +1,210 @@
+use serde::{de::IntoDeserializer, Deserialize};
+use std::{cmp::Ordering, fmt::Display};
+
+use crate::{report, ClassDependencyIndex, File};
+
+use super::SortType;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub sort_type: SortType,
+    pub direction: Direction,
+}
+
+/// Sort keys applied in turn, ties left by all of them are ordered by class name.
+/// Spelled like `hotspot,class-complexity:asc`, keys sort in descending order unless
+/// followed by `:asc`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct SortOrder {
+    pub keys: Vec<SortKey>,
+}
+
+impl SortOrder {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut keys = Vec::new();
+        for key in spec.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+            let (name, direction) = match key.split_once(':') {
+                Some((name, "asc")) => (name, Direction::Ascending),
+                Some((name, "desc")) => (name, Direction::Descending),
+                Some((_, direction)) => {
+                    return Err(format!(
+                        "Invalid sort direction {direction}, expected asc or desc"
+                    ))
+                }
+                None => (key, Direction::Descending),
+            };
+            let deserializer: serde::de::value::StrDeserializer<serde::de::value::Error> =
+                name.into_deserializer();
+            let sort_type = SortType::deserialize(deserializer)
+                .map_err(|err| format!("Invalid sort key {name}, {err}"))?;
+            keys.push(SortKey {
+                sort_type,
+                direction,
+            });
+        }
+        if keys.is_empty() {
+            return Err("Expected at least one sort key".to_string());
+        }
+        Ok(Self { keys })
+    }
+}
+
+impl From<SortType> for SortOrder {
+    fn from(sort_type: SortType) -> Self {
+        Self {
+            keys: vec![SortKey {
+                sort_type,
+                direction: Direction::Descending,
+            }],
+        }
+    }
+}
+
+impl TryFrom<String> for SortOrder {
+    type Error = String;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        Self::parse(&spec)
+    }
+}
+
+impl Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, key) in self.keys.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", then ")?;
+            }
+            write!(f, "{}", key.sort_type)?;
+            if key.direction == Direction::Ascending {
+                write!(f, " ascending")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SortKey {
+    fn compare(&self, a: &File, b: &File, index: &ClassDependencyIndex) -> Ordering {
+        let ordering = match self.sort_type {
+            SortType::ClassComplexity => a
+                .class
+                .average_complexity()
+                .total_cmp(&b.class.average_complexity()),
+            SortType::Uses => {
+                let uses = |file: &File| index.get(&file.class.name).copied().unwrap_or(0);
+                uses(a).cmp(&uses(b))
+            }
+            SortType::Dependencies => a.class.dependencies.len().cmp(&b.class.dependencies.len()),
+            SortType::FunctionComplexity => a
+                .class
+                .highest_complexity_function()
+                .cmp(&b.class.highest_complexity_function()),
+            SortType::LastModified => a.modified.cmp(&b.modified),
+            SortType::Commits => {
+                let commits = |file: &File| file.history.as_ref().map_or(0, |h| h.commits);
+                commits(a).cmp(&commits(b))
+            }
+            SortType::Authors => {
+                let authors = |file: &File| file.history.as_ref().map_or(0, |h| h.authors);
+                authors(a).cmp(&authors(b))
+            }
+            SortType::Hotspot => {
+                let score = |file| report::hotspot_score(file).unwrap_or(0.0);
+                score(a).total_cmp(&score(b))
+            }
+            SortType::Lines => a.lines.cmp(&b.lines),
+            SortType::Functions => a.class.functions.len().cmp(&b.class.functions.len()),
+        };
+        match self.direction {
+            Direction::Ascending => ordering,
+            Direction::Descending => ordering.reverse(),
+        }
+    }
+}
+
+pub fn sort_files(files: &mut [File], order: &SortOrder, index: &ClassDependencyIndex) {
+    files.sort_by(|a, b| {
+        order
+            .keys
+            .iter()
+            .fold(Ordering::Equal, |ordering, key| {
+                ordering.then_with(|| key.compare(a, b, index))
+            })
+            .then_with(|| a.class.name.cmp(&b.class.name))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let order = SortOrder::parse("hotspot, class-complexity:asc").unwrap();
+        assert_eq!(
+            vec![
+                SortKey {
+                    sort_type: SortType::Hotspot,
+                    direction: Direction::Descending,
+                },
+                SortKey {
+                    sort_type: SortType::ClassComplexity,
+                    direction: Direction::Ascending,
+                },
+            ],
+            order.keys
+        );
+        assert_eq!(
+            "hotspot score, then class complexity ascending",
+            order.to_string()
+        );
+        assert!(SortOrder::parse("uses:up").is_err());
+        assert!(SortOrder::parse("size").is_err());
+        assert!(SortOrder::parse("").is_err());
+    }
+
+    #[test]
+    fn test_sort_files() {
+        let file = |name: &str, lines: usize| File {
+            lines,
+            ..File::test(name, &[])
+        };
+        let mut files = vec![
+            file("App\\D", 10),
+            file("App\\C", 30),
+            file("App\\B", 10),
+            file("App\\A", 20),
+        ];
+        let index = ClassDependencyIndex::from([
+            ("App\\A".to_string(), 1),
+            ("App\\B".to_string(), 1),
+            ("App\\C".to_string(), 1),
+            ("App\\D".to_string(), 2),
+        ]);
+        sort_files(
+            &mut files,
+            &SortOrder::parse("uses,lines:asc").unwrap(),
+            &index,
+        );
+        let names = files
+            .iter()
+            .map(|file| file.class.name.as_str())
+            .collect::<Vec<_>>();
+        // D is used the most, the others tie on uses and go by lines
+        assert_eq!(vec!["App\\D", "App\\B", "App\\A", "App\\C"], names);
+        // Ties left by every key fall back to the class name
+        sort_files(&mut files, &SortOrder::parse("uses:asc").unwrap(), &index);
+        let names = files
+            .iter()
+            .map(|file| file.class.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["App\\A", "App\\B", "App\\C", "App\\D"], names);
+    }
+}
@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Row, Table, TableState, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{ClassDependencyIndex, Composer, File};
+
+use super::{hours_since, sort_files, Filter, SortOrder, SortType};
+
+const SORT_TYPES: [SortType; 10] = [
+    SortType::ClassComplexity,
+    SortType::FunctionComplexity,
+    SortType::Dependencies,
+    SortType::Uses,
+    SortType::Hotspot,
+    SortType::Commits,
+    SortType::Authors,
+    SortType::LastModified,
+    SortType::Lines,
+    SortType::Functions,
+];
+
+#[derive(PartialEq)]
+enum Focus {
+    Table,
+    Filter,
+    Links,
+}
+
+/// A class the detail pane links to, None when it isn't part of the scan.
+struct Link {
+    label: String,
+    target: Option<usize>,
+}
+
+struct App<'a> {
+    files: &'a mut [File],
+    index: &'a ClassDependencyIndex,
+    composer: Option<&'a Composer>,
+    sort_order: SortOrder,
+    // Position in `files` of every class, rebuilt after sorting
+    by_name: HashMap<String, usize>,
+    // Names of the classes depending on each class, by name without the leading backslash
+    dependents: HashMap<String, Vec<String>>,
+    filter: String,
+    // Why the filter doesn't parse, the last good filter stays applied meanwhile
+    filter_error: Option<String>,
+    // Positions in `files` that match the filter, in display order
+    visible: Vec<usize>,
+    table: TableState,
+    links: ListState,
+    focus: Focus,
+    detail_scroll: u16,
+    // Classes we jumped away from, to go back to
+    history: Vec<usize>,
+}
+
+/// Full-screen class browser, runs until the user quits.
+pub fn run_tui(
+    files: &mut [File],
+    index: &ClassDependencyIndex,
+    composer: Option<&Composer>,
+    sort_order: SortOrder,
+) -> std::io::Result<()> {
+    let mut dependents = HashMap::<String, Vec<String>>::new();
+    for file in files.iter() {
+        for dependency in file.class.dependencies.iter() {
+            dependents
+                .entry(dependency.trim_start_matches('\\').to_string())
+                .or_default()
+                .push(file.class.name.to_owned());
+        }
+    }
+    let mut terminal = ratatui::init();
+    let mut app = App {
+        files,
+        index,
+        composer,
+        sort_order: sort_order.clone(),
+        by_name: HashMap::new(),
+        dependents,
+        filter: String::new(),
+        filter_error: None,
+        visible: Vec::new(),
+        table: TableState::default(),
+        links: ListState::default(),
+        focus: Focus::Table,
+        detail_scroll: 0,
+        history: Vec::new(),
+    };
+    app.sort(sort_order);
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+            match self.focus {
+                Focus::Filter => match key.code {
+                    KeyCode::Char(c) => {
+                        self.filter.push(c);
+                        self.edit_filter();
+                    }
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                        self.edit_filter();
+                    }
+                    KeyCode::Esc => {
+                        self.filter.clear();
+                        self.apply_filter();
+                        self.focus = Focus::Table;
+                    }
+                    KeyCode::Enter | KeyCode::Down | KeyCode::Tab => self.focus = Focus::Table,
+                    _ => {}
+                },
+                Focus::Table => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('/') => self.focus = Focus::Filter,
+                    KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                    KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                    KeyCode::PageDown => self.move_selection(20),
+                    KeyCode::PageUp => self.move_selection(-20),
+                    KeyCode::Home | KeyCode::Char('g') => self.select_row(0),
+                    KeyCode::End | KeyCode::Char('G') => {
+                        self.select_row(self.visible.len().saturating_sub(1))
+                    }
+                    KeyCode::Char('s') => self.next_sort(1),
+                    KeyCode::Char('S') => self.next_sort(SORT_TYPES.len() - 1),
+                    KeyCode::Char('J') => self.detail_scroll = self.detail_scroll.saturating_add(1),
+                    KeyCode::Char('K') => self.detail_scroll = self.detail_scroll.saturating_sub(1),
+                    KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => {
+                        self.focus = Focus::Links;
+                        self.links.select(Some(0));
+                    }
+                    KeyCode::Backspace | KeyCode::Char('b') => self.back(),
+                    _ => {}
+                },
+                Focus::Links => match key.code {
+                    KeyCode::Char('q') => return Ok(()),
+                    KeyCode::Esc | KeyCode::Tab | KeyCode::Left | KeyCode::Char('h') => {
+                        self.focus = Focus::Table
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => self.links.select_next(),
+                    KeyCode::Up | KeyCode::Char('k') => self.links.select_previous(),
+                    KeyCode::Enter => self.follow_link(),
+                    KeyCode::Backspace | KeyCode::Char('b') => self.back(),
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.table
+            .selected()
+            .and_then(|row| self.visible.get(row).copied())
+    }
+
+    fn select_row(&mut self, row: usize) {
+        if self.visible.is_empty() {
+            self.table.select(None);
+        } else {
+            self.table.select(Some(row.min(self.visible.len() - 1)));
+        }
+        self.detail_scroll = 0;
+        self.links.select(None);
+    }
+
+    fn move_selection(&mut self, by: isize) {
+        let row = self.table.selected().unwrap_or(0);
+        self.select_row(row.saturating_add_signed(by));
+    }
+
+    /// Selects the class at `file`, clearing the filter if it hides the class.
+    fn select_file(&mut self, file: usize) {
+        if !self.visible.contains(&file) {
+            self.filter.clear();
+            self.apply_filter();
+        }
+        if let Some(row) = self.visible.iter().position(|visible| *visible == file) {
+            self.select_row(row);
+        }
+    }
+
+    /// Applies the edited filter, switching to its sort key when it names one.
+    fn edit_filter(&mut self) {
+        self.apply_filter();
+        if let Ok(Filter {
+            sort: Some(sort_order),
+            ..
+        }) = Filter::parse(&self.filter)
+        {
+            if sort_order != self.sort_order {
+                self.sort(sort_order);
+            }
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = match Filter::parse(&self.filter) {
+            Ok(filter) => filter,
+            Err(error) => {
+                self.filter_error = Some(error);
+                return;
+            }
+        };
+        self.filter_error = None;
+        let selected = self.selected();
+        self.visible = (0..self.files.len())
+            .filter(|i| filter.matches(&self.files[*i], self.index))
+            .collect();
+        let row = selected
+            .and_then(|file| self.visible.iter().position(|visible| *visible == file))
+            .unwrap_or(0);
+        self.select_row(row);
+    }
+
+    fn sort(&mut self, sort_order: SortOrder) {
+        let selected = self
+            .selected()
+            .map(|file| self.files[file].class.name.to_owned());
+        sort_files(self.files, &sort_order, self.index);
+        self.sort_order = sort_order;
+        self.by_name = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| (file.class.name.to_owned(), i))
+            .collect();
+        // Positions changed, so the history no longer points at the same classes
+        self.history.clear();
+        self.table.select(None);
+        self.apply_filter();
+        if let Some(file) = selected.and_then(|name| self.by_name.get(&name).copied()) {
+            self.select_file(file);
+        }
+    }
+
+    fn next_sort(&mut self, step: usize) {
+        let current = SORT_TYPES
+            .iter()
+            .position(|sort_type| *sort_type == self.sort_order.keys[0].sort_type)
+            .unwrap_or(0);
+        self.sort(SortOrder::from(
+            SORT_TYPES[(current + step) % SORT_TYPES.len()],
+        ));
+    }
+
+    /// Dependencies followed by dependents of the selected class.
+    fn links_of(&self, file: usize) -> Vec<Link> {
+        let class = &self.files[file].class;
+        let mut links = class
+            .dependencies
+            .iter()
+            .map(|dependency| Link {
+                label: format!("→ {dependency}"),
+                target: self
+                    .by_name
+                    .get(dependency)
+                    .or_else(|| self.by_name.get(&format!("\\{dependency}")))
+                    .copied(),
+            })
+            .collect::<Vec<_>>();
+        let dependents = self.dependents.get(class.name.trim_start_matches('\\'));
+        for dependent in dependents.into_iter().flatten() {
+            links.push(Link {
+                label: format!("← {dependent}"),
+                target: self.by_name.get(dependent).copied(),
+            });
+        }
+        links
+    }
+
+    fn follow_link(&mut self) {
+        let (Some(file), Some(link)) = (self.selected(), self.links.selected()) else {
+            return;
+        };
+        if let Some(target) = self.links_of(file).get(link).and_then(|link| link.target) {
+            self.history.push(file);
+            self.select_file(target);
+            self.focus = Focus::Table;
+        }
+    }
+
+    fn back(&mut self) {
+        if let Some(file) = self.history.pop() {
+            self.select_file(file);
+            self.focus = Focus::Table;
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(body);
+        let [detail, links] =
+            Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(right);
+
+        let filter_style = if self.filter_error.is_some() {
+            Style::new().red()
+        } else if self.focus == Focus::Filter {
+            Style::new().yellow()
+        } else {
+            Style::new()
+        };
+        let cursor = if self.focus == Focus::Filter { "_" } else { "" };
+        frame.render_widget(
+            Paragraph::new(format!("/{}{cursor}", self.filter)).block(
+                Block::bordered()
+                    .title(match &self.filter_error {
+                        Some(error) => format!(" Filter: {error} "),
+                        None => " Filter ".to_string(),
+                    })
+                    .title_top(Line::from(format!(" Sort: {} ", self.sort_order)).right_aligned())
+                    .border_style(filter_style),
+            ),
+            header,
+        );
+
+        self.draw_table(frame, left);
+        self.draw_detail(frame, detail);
+        self.draw_links(frame, links);
+
+        let help = match self.focus {
+            Focus::Filter => "type to filter  enter: done  esc: clear",
+            Focus::Table => {
+                "j/k: move  /: filter  s/S: sort  tab: links  J/K: scroll details  b: back  q: quit"
+            }
+            Focus::Links => "j/k: move  enter: go to class  b: back  tab: table  q: quit",
+        };
+        frame.render_widget(Paragraph::new(help).dark_gray(), footer);
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.visible.iter().map(|i| {
+            let file = &self.files[*i];
+            let class = &file.class;
+            Row::new(vec![
+                class.name.to_owned(),
+                format!("{:.2}", class.average_complexity()),
+                class.highest_complexity_function().to_string(),
+                class.dependencies.len().to_string(),
+                self.index.get(&class.name).unwrap_or(&0).to_string(),
+                file.lines.to_string(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(20),
+                Constraint::Length(6),
+                Constraint::Length(4),
+                Constraint::Length(5),
+                Constraint::Length(5),
+                Constraint::Length(6),
+            ],
+        )
+        .header(Row::new(["Class", "Avg", "Max", "Deps", "Uses", "Lines"]).bold())
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(format!(
+            " Classes {}/{} ",
+            self.visible.len(),
+            self.files.len()
+        )));
+        frame.render_stateful_widget(table, area, &mut self.table);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Details ");
+        let Some(file) = self.selected().map(|i| &self.files[i]) else {
+            frame.render_widget(Paragraph::new("No class selected").block(block), area);
+            return;
+        };
+        let class = &file.class;
+        let mut lines = vec![
+            Line::from(class.name.to_owned()).bold(),
+            Line::from(format!("Path: {}", file.path)),
+        ];
+        if let Some(composer) = self.composer {
+            let package = composer.package_for(&class.name).unwrap_or("Unknown");
+            lines.push(Line::from(format!("Package: {package}")));
+        }
+        if let Some(expected) = &file.autoload_mismatch {
+            lines.push(Line::from(format!("Autoload mismatch, expected {expected}")).red());
+        }
+        lines.push(Line::from(format!(
+            "Lines: {}  Used in {} places  Last modified {} hours ago",
+            file.lines,
+            self.index.get(&class.name).unwrap_or(&0),
+            hours_since(file.modified)
+        )));
+        if let Some(history) = &file.history {
+            lines.push(Line::from(format!(
+                "Commits: {} by {} authors",
+                history.commits, history.authors
+            )));
+        }
+        lines.push(Line::from(format!(
+            "Extends: {}  Abstract: {}",
+            class.extends.as_deref().unwrap_or("None"),
+            class.is_abstract
+        )));
+        if !class.implements.is_empty() {
+            lines.push(Line::from(format!(
+                "Implements: {}",
+                class.implements.join(", ")
+            )));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("Functions ({})", class.functions.len())).bold());
+        for function in class.functions.iter() {
+            let complexity = function.complexity();
+            let style = if complexity > 10 {
+                Style::new().red()
+            } else {
+                Style::new()
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:>3} ", complexity), style),
+                Span::raw(format!(
+                    "{} {}({} params){}  line {}",
+                    function.visibility,
+                    function.name,
+                    function.params,
+                    function
+                        .return_type
+                        .as_ref()
+                        .map_or(String::new(), |return_type| format!(": {return_type}")),
+                    function.line
+                )),
+            ]));
+        }
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false })
+                .scroll((self.detail_scroll, 0)),
+            area,
+        );
+    }
+
+    fn draw_links(&mut self, frame: &mut Frame, area: Rect) {
+        let links = self
+            .selected()
+            .map(|file| self.links_of(file))
+            .unwrap_or_default();
+        let items = links
+            .iter()
+            .map(|link| {
+                let item = ListItem::new(link.label.to_owned());
+                if link.target.is_some() {
+                    item
+                } else {
+                    item.dark_gray()
+                }
+            })
+            .collect::<Vec<_>>();
+        let border = if self.focus == Focus::Links {
+            Style::new().yellow()
+        } else {
+            Style::new()
+        };
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(" Dependencies → and dependents ← ")
+                    .border_style(border),
+            )
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.links);
+    }
+}
@@ -0,0 +1,92 @@
+use colored::Colorize;
+use std::{cmp::Ordering, thread, time::Duration};
+
+use crate::{
+    indexing::{Change, Watcher},
+    ClassDependencyIndex, File,
+};
+
+use super::{io, sort_files, SortOrder, ViewOptions};
+
+pub fn run_watch(
+    watcher: &mut Watcher,
+    index: &mut ClassDependencyIndex,
+    files: &mut Vec<File>,
+    interval: Duration,
+    sort_order: &SortOrder,
+    view_options: &ViewOptions,
+) -> ! {
+    sort_files(files, sort_order, index);
+    display_top_files(files, index, view_options.top_files);
+    println!(
+        "\nWatching for changes every {} seconds, press Ctrl+C to stop.",
+        interval.as_secs_f64()
+    );
+
+    loop {
+        thread::sleep(interval);
+        let (changes, errors) = watcher.poll(index, files);
+        for err in errors.iter() {
+            eprintln!("ERROR: {err}");
+        }
+        if changes.is_empty() {
+            continue;
+        }
+        io::display_title("Changes");
+        for change in changes.iter() {
+            display_change(change);
+        }
+        sort_files(files, sort_order, index);
+        display_top_files(files, index, view_options.top_files);
+    }
+}
+
+fn display_change(change: &Change) {
+    match (&change.before, &change.after) {
+        (None, Some(after)) => println!(
+            "Added {} in {}, average {:.2}, max {}",
+            after.name,
+            change.path,
+            after.average_complexity(),
+            after.highest_complexity_function()
+        ),
+        (Some(before), None) => println!("Removed {} from {}", before.name, change.path),
+        (Some(before), Some(after)) => {
+            let message = format!(
+                "{}: average {:.2} -> {:.2}, max {} -> {}",
+                after.name,
+                before.average_complexity(),
+                after.average_complexity(),
+                before.highest_complexity_function(),
+                after.highest_complexity_function()
+            );
+            let average = after
+                .average_complexity()
+                .total_cmp(&before.average_complexity());
+            let max = after
+                .highest_complexity_function()
+                .cmp(&before.highest_complexity_function());
+            match average.then(max) {
+                Ordering::Greater => println!("{} {}", message.red(), "(worse)".red()),
+                Ordering::Less => println!("{} {}", message.green(), "(better)".green()),
+                Ordering::Equal => println!("{message}"),
+            }
+        }
+        (None, None) => (),
+    }
+}
+
+fn display_top_files(files: &[File], index: &ClassDependencyIndex, top_files: usize) {
+    io::display_title("Top Files");
+    for (i, file) in files.iter().take(top_files).enumerate() {
+        let class = &file.class;
+        println!(
+            "{}. {} average {:.2}, max {}, used in {} places",
+            i + 1,
+            class.name,
+            class.average_complexity(),
+            class.highest_complexity_function(),
+            index.get(&class.name).unwrap_or(&0)
+        );
+    }
+}
@@ -1,30 +1,448 @@
-use indexing::{ClassDependencyIndex, File};
+use indexing::{ClassDependencyIndex, Composer, File, Watcher};
 
-use crate::{indexing::index, interface::run_program};
-use std::{env, fs, process};
+use crate::{
+    cli::Args,
+    config::{Config, OutputFormat},
+    indexing::{commits, index, load_scan, mark_changed, save_scan, IndexOptions, Worktree},
+    interface::{
+        display_diff, display_files, display_functions, display_profile, display_violations,
+        run_program, run_watch, sort_files, SortOrder, SortType, ViewOptions,
+    },
+    report::{Baseline, TrendPoint, Violation},
+};
+use serde::Serialize;
+use signal_hook::{consts::SIGINT, flag};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
+mod cli;
+mod config;
 mod indexing;
 mod interface;
+mod report;
+
+const BASELINE_FILE: &str = "tcplscan-baseline.json";
+const HTML_DIR: &str = "tcplscan-report";
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let args = Args::parse();
+
+    let config = Config::discover(&args.path, args.config.as_deref()).unwrap_or_default();
+    if let Some(before) = &args.diff {
+        diff(&args, &config, before);
+    }
 
-    let path = args.get(1).unwrap_or_else(|| {
-        eprintln!("ERROR: Please input file path to scan");
+    if let Err(err) = fs::read_dir(&args.path) {
+        eprintln!("ERROR: Failed to read directory, {err}");
         process::exit(1);
+    }
+
+    let format = args.format.or(config.format).unwrap_or_default();
+    if let Some(range) = &args.trend {
+        trend(&args, &config, range, format);
+    }
+
+    let mut sort_order = args
+        .sort
+        .clone()
+        .or(config.sort.clone())
+        .unwrap_or_else(|| SortOrder::from(SortType::ClassComplexity));
+    let view_options = ViewOptions::from_config(&config.view);
+
+    let composer = discover_composer(&args.path);
+    let options = index_options(&args, &config, composer.as_ref());
+    // Snapshot before indexing, so files edited while indexing show up in the first poll
+    let mut watcher = args.watch.then(|| Watcher::new(&args.path, &options));
+    let (mut index, mut files) = index(&args.path, &options);
+
+    if let Some(query) = &args.show {
+        show(&args, &index, &files, composer.as_ref(), query, format);
+    }
+
+    if let Some(path) = &args.save_scan {
+        save_scan(path, &files);
+        eprintln!("Saved scan to {}", path.display());
+        if !args.check && !args.update_baseline && !args.prune_baseline {
+            process::exit(0);
+        }
+    }
+
+    // The baseline covers the whole tree, so it is never written from narrowed violations
+    if args.update_baseline || args.prune_baseline {
+        let violations = report::violations(&files, &config.thresholds, &config.layers);
+        check(&args, &config, &files, violations, format);
+    }
+
+    let changed = args.changed_since.as_ref().map(|base| {
+        let changed = mark_changed(&args.path, base, &mut files).unwrap_or_else(|err| {
+            eprintln!("ERROR: Failed to find the files changed since {base}, {err}");
+            process::exit(1);
+        });
+        eprintln!("Found {} classes changed since {base}.", changed.len());
+        changed
     });
+    // Violations are found in the whole tree so cycles through unchanged classes count
+    let mut violations = args
+        .check
+        .then(|| report::violations(&files, &config.thresholds, &config.layers));
 
-    let dir_entry = fs::read_dir(path).unwrap_or_else(|err| {
-        eprintln!("ERROR: Failed to read directory, {err}");
+    // Usages and dependencies still come from the whole tree, only the report is narrowed
+    if let Some(changed) = &changed {
+        files.retain(|file| changed.contains(&file.path));
+        if let Some(violations) = &mut violations {
+            violations.retain(|violation| changed.contains(&violation.path));
+        }
+    }
+
+    if let Some(violations) = violations {
+        check(&args, &config, &files, violations, format);
+    }
+
+    // Like --changed-since this narrows the listed classes, not the index
+    if let Some(filter) = &args.filter {
+        files.retain(|file| filter.matches(file, &index));
+        if let Some(filter_order) = &filter.sort {
+            sort_order = filter_order.clone();
+        }
+        eprintln!("Found {} classes matching the filter.", files.len());
+    }
+
+    if args.report {
+        sort_files(&mut files, &sort_order, &index);
+        report(
+            &args,
+            &index,
+            &files,
+            composer.as_ref(),
+            format,
+            &view_options,
+        );
+    }
+
+    if let Some(watcher) = &mut watcher {
+        run_watch(
+            watcher,
+            &mut index,
+            &mut files,
+            args.interval,
+            &sort_order,
+            &view_options,
+        );
+    }
+
+    run_program(
+        &index,
+        &mut files,
+        composer.as_ref(),
+        sort_order,
+        view_options,
+        !args.no_tui,
+    );
+}
+
+fn discover_composer(path: &str) -> Option<Composer> {
+    let composer = Composer::discover(path);
+    if let Some(composer) = &composer {
+        eprintln!(
+            "Found composer project {} with {} installed packages.",
+            composer.name,
+            composer.packages.len()
+        );
+    }
+    composer
+}
+
+/// Index options from the command line, falling back to the config file.
+fn index_options<'a>(
+    args: &Args,
+    config: &Config,
+    composer: Option<&'a Composer>,
+) -> IndexOptions<'a> {
+    IndexOptions {
+        composer,
+        include_vendor: args.include_vendor || config.include_vendor,
+        paths: config.paths(),
+        include: [config.include.clone(), args.include.clone()].concat(),
+        exclude: [config.exclude.clone(), args.exclude.clone()].concat(),
+        extensions: args
+            .extensions
+            .clone()
+            .or(config.extensions.clone())
+            .unwrap_or_else(|| vec!["php".to_string()]),
+        threads: args.threads,
+        cache_path: args.cache_path(),
+        clear_cache: args.clear_cache,
+        histories: true,
+    }
+}
+
+/// Compares the scanned path against `before`, either of which can be a directory or a
+/// scan saved with `--save-scan`.
+fn diff(args: &Args, config: &Config, before: &str) -> ! {
+    let before = load_or_index(args, config, before);
+    let after = load_or_index(args, config, &args.path);
+    let diff = report::diff((&before.0, &before.1), (&after.0, &after.1));
+    display_diff(&diff);
+    process::exit(0);
+}
+
+fn load_or_index(args: &Args, config: &Config, path: &str) -> (ClassDependencyIndex, Vec<File>) {
+    if Path::new(path).is_file() {
+        return load_scan(Path::new(path));
+    }
+    if let Err(err) = fs::read_dir(path) {
+        eprintln!("ERROR: Failed to read directory {path}, {err}");
+        process::exit(1);
+    }
+    let composer = discover_composer(path);
+    let mut options = index_options(args, config, composer.as_ref());
+    if path != args.path {
+        // The config describes the layout of the scanned directory, apply it to the other one
+        options.paths = config
+            .paths
+            .iter()
+            .map(|p| Path::new(path).join(p))
+            .collect();
+        options.cache_path = None;
+    }
+    index(path, &options)
+}
+
+/// Scans every commit in `range` and writes the complexity over time.
+fn trend(args: &Args, config: &Config, range: &str, format: OutputFormat) -> ! {
+    let points = scan_commits(args, config, range).unwrap_or_else(|err| {
+        eprintln!("ERROR: Failed to scan the commits in {range}, {err}");
         process::exit(1);
     });
+    let content = match format {
+        OutputFormat::Json => to_json(&points),
+        OutputFormat::Text | OutputFormat::Csv => report::trend_csv(&points),
+        OutputFormat::Sarif
+        | OutputFormat::Checkstyle
+        | OutputFormat::Junit
+        | OutputFormat::Html
+        | OutputFormat::Markdown => {
+            eprintln!("ERROR: --trend doesn't support the {format} format");
+            process::exit(1);
+        }
+    };
+    write_output(args, &content);
+    process::exit(0);
+}
 
-    let (index, mut files) = index(dir_entry);
+fn scan_commits(args: &Args, config: &Config, range: &str) -> Result<Vec<TrendPoint>, String> {
+    let path = Path::new(&args.path);
+    let commits = commits(path, range)?;
+    let first = commits.first().ok_or("no commits found")?;
+    // The first Ctrl+C stops after the current commit so the worktree gets removed, a
+    // second one exits right away
+    let interrupted = Arc::new(AtomicBool::new(false));
+    flag::register_conditional_shutdown(SIGINT, 1, Arc::clone(&interrupted))
+        .and_then(|_| flag::register(SIGINT, Arc::clone(&interrupted)))
+        .map_err(|err| err.to_string())?;
+    let worktree = Worktree::new(path, &first.hash)?;
+    let mut points = Vec::new();
+    for (i, commit) in commits.iter().enumerate() {
+        if interrupted.load(Ordering::Relaxed) {
+            return Err("interrupted".to_string());
+        }
+        worktree.checkout(&commit.hash)?;
+        eprintln!(
+            "Scanning commit {} of {}, {}",
+            i + 1,
+            commits.len(),
+            commit.hash
+        );
+        let tree = worktree.path();
+        let tree_path = tree.to_str().ok_or("invalid worktree path")?;
+        let composer = Composer::discover(tree_path);
+        let mut options = index_options(args, config, composer.as_ref());
+        options.paths = config.paths.iter().map(|p| tree.join(p)).collect();
+        options.cache_path = Some(worktree.cache_path());
+        options.clear_cache = false;
+        // Trend points don't use the history and reading it costs a git log per commit
+        options.histories = false;
+        let (_, files) = index(tree_path, &options);
+        points.push(report::trend_point(&commit.hash, &commit.date, &files));
+    }
+    Ok(points)
+}
 
-    run_program(&index, &mut files);
+fn to_json<T: Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|err| {
+        eprintln!("ERROR: Failed to serialize output, {err}");
+        process::exit(1);
+    })
+}
+
+/// Writes machine readable output to `--output`, or stdout without it.
+fn write_output(args: &Args, content: &str) {
+    let path = match &args.output {
+        Some(path) => path,
+        None => {
+            println!("{}", content.trim_end());
+            return;
+        }
+    };
+    if let Err(err) = fs::write(path, content) {
+        eprintln!("ERROR: Failed to write {}, {err}", path.display());
+        process::exit(1);
+    }
+    eprintln!("Wrote {}", path.display());
 }
 
-fn error(msg: &str, line: usize) {
-    eprintln!("ERR: line: {line} {msg}");
-    process::exit(1);
+/// Writes the scanned classes in `format` instead of starting the interactive view.
+fn report(
+    args: &Args,
+    index: &ClassDependencyIndex,
+    files: &[File],
+    composer: Option<&Composer>,
+    format: OutputFormat,
+    view_options: &ViewOptions,
+) -> ! {
+    if args.functions {
+        report_functions(args, files, format, view_options);
+    }
+    match format {
+        OutputFormat::Text => display_files(files, index, composer, view_options),
+        OutputFormat::Json => write_output(args, &to_json(files)),
+        OutputFormat::Html => {
+            let dir = args
+                .output
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(HTML_DIR));
+            if let Err(err) = report::html(&dir, files, index) {
+                eprintln!("ERROR: Failed to write report {}, {err}", dir.display());
+                process::exit(1);
+            }
+            eprintln!("Wrote report to {}", dir.join("index.html").display());
+        }
+        OutputFormat::Csv | OutputFormat::Markdown => {
+            let table = report::class_table(files, index);
+            let content = match format {
+                OutputFormat::Csv => table.to_csv(),
+                _ => table.to_markdown(),
+            };
+            write_output(args, &content);
+        }
+        OutputFormat::Sarif | OutputFormat::Checkstyle | OutputFormat::Junit => {
+            eprintln!("ERROR: --report doesn't support the {format} format");
+            process::exit(1);
+        }
+    }
+    process::exit(0);
+}
+
+/// Prints everything about the class named by `query`, or the class in the file at it.
+fn show(
+    args: &Args,
+    index: &ClassDependencyIndex,
+    files: &[File],
+    composer: Option<&Composer>,
+    query: &str,
+    format: OutputFormat,
+) -> ! {
+    let file = report::find_class(files, query).unwrap_or_else(|| {
+        eprintln!("ERROR: No class or file named {query}");
+        process::exit(1);
+    });
+    let profile = report::profile(file, files, index);
+    match format {
+        OutputFormat::Text => display_profile(&profile, composer),
+        OutputFormat::Json => write_output(args, &to_json(&profile)),
+        _ => {
+            eprintln!("ERROR: --show doesn't support the {format} format");
+            process::exit(1);
+        }
+    }
+    process::exit(0);
+}
+
+/// Writes every function in the project ranked by `--functions-by`.
+fn report_functions(
+    args: &Args,
+    files: &[File],
+    format: OutputFormat,
+    view_options: &ViewOptions,
+) -> ! {
+    let functions = report::rank_functions(files, args.functions_by);
+    match format {
+        OutputFormat::Text => display_functions(&functions, args.functions_by, view_options),
+        OutputFormat::Json => write_output(args, &to_json(&functions)),
+        OutputFormat::Csv => write_output(args, &report::function_table(&functions).to_csv()),
+        OutputFormat::Markdown => {
+            write_output(args, &report::function_table(&functions).to_markdown())
+        }
+        _ => {
+            eprintln!("ERROR: --functions doesn't support the {format} format");
+            process::exit(1);
+        }
+    }
+    process::exit(0);
+}
+
+/// Reports the threshold and layer violations for CI, only the ones missing from the
+/// baseline, or writes and prunes the baseline.
+fn check(
+    args: &Args,
+    config: &Config,
+    files: &[File],
+    violations: Vec<Violation>,
+    format: OutputFormat,
+) -> ! {
+    let baseline_path = args
+        .baseline
+        .clone()
+        .or(config.baseline())
+        .unwrap_or_else(|| PathBuf::from(&args.path).join(BASELINE_FILE));
+    let baseline = Baseline::load(&baseline_path);
+
+    if args.update_baseline {
+        Baseline::from_violations(&violations).save(&baseline_path);
+        eprintln!(
+            "Wrote {} violations to baseline {}",
+            violations.len(),
+            baseline_path.display()
+        );
+        process::exit(0);
+    }
+    if args.prune_baseline {
+        let mut baseline = baseline.unwrap_or_else(|| {
+            eprintln!("ERROR: No baseline found at {}", baseline_path.display());
+            process::exit(1);
+        });
+        let pruned = baseline.prune(&violations);
+        baseline.save(&baseline_path);
+        eprintln!(
+            "Pruned {pruned} fixed violations from baseline {}",
+            baseline_path.display()
+        );
+        process::exit(0);
+    }
+
+    let violations = match &baseline {
+        Some(baseline) => baseline.filter(violations),
+        None => violations,
+    };
+    match format {
+        OutputFormat::Text => display_violations(&violations),
+        OutputFormat::Json => write_output(args, &to_json(&violations)),
+        OutputFormat::Sarif => {
+            write_output(args, &to_json(&report::sarif(&violations, &args.path)))
+        }
+        OutputFormat::Checkstyle => write_output(args, &report::checkstyle(&violations)),
+        OutputFormat::Junit => write_output(args, &report::junit(files, &violations)),
+        OutputFormat::Csv | OutputFormat::Html | OutputFormat::Markdown => {
+            eprintln!("ERROR: --check doesn't support the {format} format");
+            process::exit(1);
+        }
+    }
+    process::exit(if violations.is_empty() { 0 } else { 1 });
 }
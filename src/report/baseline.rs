@@ -0,0 +1,126 @@
+use std::{fs, path::Path, process};
+
+use serde::{Deserialize, Serialize};
+
+use crate::report::violations::{Rule, Violation};
+
+/// An accepted violation, matched by rule, class and function or dependency.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BaselineEntry {
+    pub rule: Rule,
+    pub class: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub value: f64,
+}
+
+impl BaselineEntry {
+    fn matches(&self, violation: &Violation) -> bool {
+        self.rule == violation.rule
+            && self.class == violation.class
+            && self.subject == violation.subject
+    }
+}
+
+/// Violations that already existed when the baseline was written.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub violations: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn from_violations(violations: &[Violation]) -> Self {
+        let mut violations = violations
+            .iter()
+            .map(|violation| BaselineEntry {
+                rule: violation.rule,
+                class: violation.class.to_owned(),
+                subject: violation.subject.to_owned(),
+                value: violation.value,
+            })
+            .collect::<Vec<_>>();
+        violations
+            .sort_by(|a, b| (&a.class, a.rule, &a.subject).cmp(&(&b.class, b.rule, &b.subject)));
+        Self { violations }
+    }
+
+    /// Loads the baseline, None if there is no baseline file yet.
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(baseline) => Some(baseline),
+            Err(err) => {
+                eprintln!("ERROR: Failed to parse baseline {}, {err}", path.display());
+                process::exit(1);
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) {
+        let content = serde_json::to_string_pretty(self).unwrap_or_else(|err| {
+            eprintln!("ERROR: Failed to serialize baseline, {err}");
+            process::exit(1);
+        });
+        if let Err(err) = fs::write(path, content + "\n") {
+            eprintln!("ERROR: Failed to write baseline {}, {err}", path.display());
+            process::exit(1);
+        }
+    }
+
+    /// Keeps the violations that are new or got worse than their baseline value.
+    pub fn filter(&self, violations: Vec<Violation>) -> Vec<Violation> {
+        violations
+            .into_iter()
+            .filter(|violation| {
+                !self
+                    .violations
+                    .iter()
+                    .any(|entry| entry.matches(violation) && violation.value <= entry.value)
+            })
+            .collect()
+    }
+
+    /// Drops the entries that no longer have a violation and returns how many were dropped.
+    pub fn prune(&mut self, violations: &[Violation]) -> usize {
+        let before = self.violations.len();
+        self.violations
+            .retain(|entry| violations.iter().any(|violation| entry.matches(violation)));
+        before - self.violations.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violation(class: &str, function: &str, value: f64) -> Violation {
+        Violation {
+            rule: Rule::FunctionComplexity,
+            class: class.to_string(),
+            path: String::new(),
+            subject: Some(function.to_string()),
+            value,
+            limit: 5.0,
+            message: String::new(),
+            line: 0,
+            related_lines: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_filter_and_prune() {
+        let mut baseline =
+            Baseline::from_violations(&[violation("A", "run", 8.0), violation("B", "run", 6.0)]);
+
+        let current = vec![
+            violation("A", "run", 8.0),
+            violation("A", "stop", 7.0),
+            violation("B", "run", 9.0),
+        ];
+        let remaining = baseline.filter(current.clone());
+        assert_eq!(vec![current[1].clone(), current[2].clone()], remaining);
+
+        assert_eq!(1, baseline.prune(&current[1..]));
+        assert_eq!("B", baseline.violations[0].class);
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use crate::File;
+
+/// Groups of classes that depend on each other in a circle, found with Tarjan's strongly
+/// connected components. Only classes of the scanned project are considered.
+pub fn cycles(files: &[File]) -> Vec<Vec<String>> {
+    let names = files
+        .iter()
+        .map(|file| file.class.name.trim_start_matches('\\'))
+        .collect::<Vec<_>>();
+    let ids = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (*name, i))
+        .collect::<HashMap<_, _>>();
+    let edges = files
+        .iter()
+        .map(|file| {
+            file.class
+                .dependencies
+                .iter()
+                .filter_map(|dependency| ids.get(dependency.trim_start_matches('\\')).copied())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut tarjan = Tarjan {
+        edges: &edges,
+        index: vec![None; files.len()],
+        low: vec![0; files.len()],
+        on_stack: vec![false; files.len()],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for node in 0..files.len() {
+        if tarjan.index[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+
+    let mut cycles = tarjan
+        .components
+        .into_iter()
+        .filter(|component| component.len() > 1 || edges[component[0]].contains(&component[0]))
+        .map(|component| {
+            let mut cycle = component
+                .into_iter()
+                .map(|node| names[node].to_string())
+                .collect::<Vec<_>>();
+            cycle.sort();
+            cycle
+        })
+        .collect::<Vec<_>>();
+    cycles.sort();
+    cycles
+}
+
+struct Tarjan<'a> {
+    edges: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    /// Visits everything reachable from `root`. The path is kept on an explicit stack of
+    /// nodes and the next edge to follow, so long dependency chains can't overflow the
+    /// call stack.
+    fn visit(&mut self, root: usize) {
+        self.enter(root);
+        let mut path = vec![(root, 0)];
+        while let Some((node, edge)) = path.last_mut() {
+            let node = *node;
+            if let Some(&next) = self.edges[node].get(*edge) {
+                *edge += 1;
+                match self.index[next] {
+                    None => {
+                        self.enter(next);
+                        path.push((next, 0));
+                    }
+                    Some(index) if self.on_stack[next] => {
+                        self.low[node] = self.low[node].min(index);
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            path.pop();
+            if let Some(&(parent, _)) = path.last() {
+                self.low[parent] = self.low[parent].min(self.low[node]);
+            }
+            if Some(self.low[node]) == self.index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    fn enter(&mut self, node: usize) {
+        self.index[node] = Some(self.next);
+        self.low[node] = self.next;
+        self.next += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycles() {
+        let files = vec![
+            File::test("App\\A", &["App\\B"]),
+            File::test("App\\B", &["\\App\\A"]),
+            File::test("App\\C", &["App\\C"]),
+            File::test("App\\D", &["App\\A", "Vendor\\E"]),
+        ];
+        assert_eq!(
+            vec![
+                vec!["App\\A".to_string(), "App\\B".to_string()],
+                vec!["App\\C".to_string()],
+            ],
+            cycles(&files)
+        );
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let names = (0..50_000)
+            .map(|i| format!("App\\C{i}"))
+            .collect::<Vec<_>>();
+        let files = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| File::test(name, &[&names[(i + 1) % names.len()]]))
+            .collect::<Vec<_>>();
+        let cycles = cycles(&files);
+        assert_eq!(1, cycles.len());
+        assert_eq!(names.len(), cycles[0].len());
+    }
+}
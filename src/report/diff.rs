@@ -0,0 +1,189 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::{ClassDependencyIndex, File};
+
+/// How a class changed between two scans, `before` and `after` pairs are (old, new).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassChange {
+    pub class: String,
+    pub average_complexity: (f64, f64),
+    pub max_complexity: (usize, usize),
+    pub added_dependencies: Vec<String>,
+    pub removed_dependencies: Vec<String>,
+    pub uses: (usize, usize),
+}
+
+impl ClassChange {
+    /// Positive when the class got worse: more complexity or more dependencies.
+    pub fn regression(&self) -> f64 {
+        (self.average_complexity.1 - self.average_complexity.0)
+            + (self.max_complexity.1 as f64 - self.max_complexity.0 as f64)
+            + (self.added_dependencies.len() as f64 - self.removed_dependencies.len() as f64)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ScanDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Sorted by regression, the biggest regression first
+    pub changed: Vec<ClassChange>,
+}
+
+/// Compares two scans class by class.
+pub fn diff(
+    before: (&ClassDependencyIndex, &[File]),
+    after: (&ClassDependencyIndex, &[File]),
+) -> ScanDiff {
+    let (before_index, before_files) = before;
+    let (after_index, after_files) = after;
+    let old = before_files
+        .iter()
+        .map(|file| (file.class.name.as_str(), file))
+        .collect::<HashMap<_, _>>();
+    let new = after_files
+        .iter()
+        .map(|file| (file.class.name.as_str(), file))
+        .collect::<HashMap<_, _>>();
+
+    let mut diff = ScanDiff::default();
+    for (name, file) in new.iter() {
+        let old_file = match old.get(name) {
+            Some(old_file) => old_file,
+            None => {
+                diff.added.push(name.to_string());
+                continue;
+            }
+        };
+        let (old_class, new_class) = (&old_file.class, &file.class);
+        let change = ClassChange {
+            class: name.to_string(),
+            average_complexity: (
+                old_class.average_complexity(),
+                new_class.average_complexity(),
+            ),
+            max_complexity: (
+                old_class.highest_complexity_function(),
+                new_class.highest_complexity_function(),
+            ),
+            added_dependencies: new_class
+                .dependencies
+                .iter()
+                .filter(|dependency| !old_class.dependencies.contains(*dependency))
+                .cloned()
+                .collect(),
+            removed_dependencies: old_class
+                .dependencies
+                .iter()
+                .filter(|dependency| !new_class.dependencies.contains(*dependency))
+                .cloned()
+                .collect(),
+            uses: (
+                *before_index.get(*name).unwrap_or(&0),
+                *after_index.get(*name).unwrap_or(&0),
+            ),
+        };
+        let unchanged = change.average_complexity.0 == change.average_complexity.1
+            && change.max_complexity.0 == change.max_complexity.1
+            && change.added_dependencies.is_empty()
+            && change.removed_dependencies.is_empty()
+            && change.uses.0 == change.uses.1;
+        if !unchanged {
+            diff.changed.push(change);
+        }
+    }
+    diff.removed = old
+        .keys()
+        .filter(|name| !new.contains_key(*name))
+        .map(|name| name.to_string())
+        .collect();
+
+    diff.added.sort();
+    diff.removed.sort();
+    for change in diff.changed.iter_mut() {
+        change.added_dependencies.sort();
+        change.removed_dependencies.sort();
+    }
+    diff.changed.sort_by(|a, b| {
+        b.regression()
+            .partial_cmp(&a.regression())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.class.cmp(&b.class))
+    });
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexing::Function;
+
+    fn file(name: &str, dependencies: &[&str], complexities: &[usize]) -> File {
+        let mut file = File::test(name, dependencies);
+        file.class.functions = complexities
+            .iter()
+            .map(|complexity| Function::test("run", *complexity))
+            .collect();
+        file
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = [
+            file("App\\Invoice", &["App\\Money", "App\\Tax"], &[2]),
+            file("App\\Money", &[], &[1]),
+            file("App\\Cart", &[], &[4]),
+            file("App\\Mailer", &[], &[1]),
+            file("App\\Legacy", &[], &[1]),
+        ];
+        let after = [
+            file("App\\Invoice", &["App\\Money", "App\\Currency"], &[5, 1]),
+            file("App\\Money", &[], &[1]),
+            file("App\\Cart", &[], &[2]),
+            file("App\\Mailer", &[], &[1]),
+            file("App\\Report", &["App\\Mailer"], &[1]),
+        ];
+        let before_index = ClassDependencyIndex::from([("App\\Money".to_string(), 1)]);
+        let after_index = ClassDependencyIndex::from([
+            ("App\\Money".to_string(), 1),
+            ("App\\Mailer".to_string(), 1),
+        ]);
+
+        let diff = diff((&before_index, &before), (&after_index, &after));
+        assert_eq!(vec!["App\\Report"], diff.added);
+        assert_eq!(vec!["App\\Legacy"], diff.removed);
+        assert_eq!(
+            vec!["App\\Invoice", "App\\Mailer", "App\\Cart"],
+            diff.changed
+                .iter()
+                .map(|change| change.class.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            ClassChange {
+                class: "App\\Invoice".to_string(),
+                average_complexity: (2.0, 3.0),
+                max_complexity: (2, 5),
+                added_dependencies: vec!["App\\Currency".to_string()],
+                removed_dependencies: vec!["App\\Tax".to_string()],
+                uses: (0, 0),
+            },
+            diff.changed[0]
+        );
+        assert_eq!((0, 1), diff.changed[1].uses);
+        assert_eq!(-4.0, diff.changed[2].regression());
+    }
+
+    #[test]
+    fn test_regression() {
+        let change = ClassChange {
+            class: "App\\Invoice".to_string(),
+            average_complexity: (2.0, 3.5),
+            max_complexity: (4, 6),
+            added_dependencies: vec!["App\\Tax".to_string()],
+            removed_dependencies: Vec::new(),
+            uses: (3, 1),
+        };
+        assert_eq!(4.5, change.regression());
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::File;
+
+/// What functions are ranked by, the largest first.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FunctionMetric {
+    #[default]
+    Complexity,
+    Params,
+    Length,
+    Nesting,
+}
+
+impl Display for FunctionMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FunctionMetric::Complexity => "cyclomatic complexity",
+                FunctionMetric::Params => "parameters",
+                FunctionMetric::Length => "length",
+                FunctionMetric::Nesting => "nesting",
+            }
+        )
+    }
+}
+
+/// A function of any class in the project, with where to find it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RankedFunction {
+    pub class: String,
+    pub function: String,
+    pub path: String,
+    pub line: usize,
+    pub visibility: String,
+    pub return_type: Option<String>,
+    pub complexity: usize,
+    pub params: usize,
+    pub length: usize,
+    pub nesting: usize,
+}
+
+impl RankedFunction {
+    fn value(&self, metric: FunctionMetric) -> usize {
+        match metric {
+            FunctionMetric::Complexity => self.complexity,
+            FunctionMetric::Params => self.params,
+            FunctionMetric::Length => self.length,
+            FunctionMetric::Nesting => self.nesting,
+        }
+    }
+}
+
+/// Every function in the project ranked by `metric`, ties broken by complexity and
+/// then by where they are.
+pub fn rank_functions(files: &[File], metric: FunctionMetric) -> Vec<RankedFunction> {
+    let mut functions = files
+        .iter()
+        .flat_map(|file| {
+            let changed = file.class.functions.iter().filter(|f| file.is_changed(f));
+            changed.map(|function| RankedFunction {
+                class: file.class.name.to_owned(),
+                function: function.name.to_owned(),
+                path: file.path.to_owned(),
+                line: function.line,
+                visibility: function.visibility.to_string(),
+                return_type: function.return_type.to_owned(),
+                complexity: function.complexity(),
+                params: function.params,
+                length: function.length,
+                nesting: function.nesting,
+            })
+        })
+        .collect::<Vec<_>>();
+    functions.sort_by(|a, b| {
+        b.value(metric)
+            .cmp(&a.value(metric))
+            .then_with(|| b.complexity.cmp(&a.complexity))
+            .then_with(|| a.class.cmp(&b.class))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+    functions
+}
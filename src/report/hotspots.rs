@@ -0,0 +1,90 @@
+use std::cmp::Ordering;
+
+use crate::File;
+
+/// A class that is both complex and changed often.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hotspot {
+    pub class: String,
+    pub path: String,
+    pub commits: usize,
+    pub average_complexity: f64,
+    pub max_complexity: usize,
+    pub score: f64,
+}
+
+/// Commits times the mean of the average and max function complexity, None for files
+/// without git history.
+pub fn hotspot_score(file: &File) -> Option<f64> {
+    let history = file.history.as_ref()?;
+    let complexity =
+        (file.class.average_complexity() + file.class.highest_complexity_function() as f64) / 2.0;
+    Some(history.commits as f64 * complexity)
+}
+
+/// Classes ranked by hotspot score, the hottest first.
+pub fn hotspots(files: &[File]) -> Vec<Hotspot> {
+    let mut hotspots = files
+        .iter()
+        .filter_map(|file| {
+            Some(Hotspot {
+                class: file.class.name.to_owned(),
+                path: file.path.to_owned(),
+                commits: file.history.as_ref()?.commits,
+                average_complexity: file.class.average_complexity(),
+                max_complexity: file.class.highest_complexity_function(),
+                score: hotspot_score(file)?,
+            })
+        })
+        .collect::<Vec<_>>();
+    hotspots.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.class.cmp(&b.class))
+    });
+    hotspots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexing::{Function, History};
+
+    #[test]
+    fn test_hotspots() {
+        let file = |name: &str, commits: Option<usize>, complexities: &[usize]| {
+            let mut file = File::test(name, &[]);
+            file.class.functions = complexities
+                .iter()
+                .map(|complexity| Function::test("run", *complexity))
+                .collect();
+            file.history = commits.map(|commits| History {
+                commits,
+                authors: 1,
+                last_commit: 0,
+            });
+            file
+        };
+        let files = [
+            file("App\\Invoice", Some(10), &[3, 1]),
+            file("App\\Mailer", Some(2), &[9]),
+            file("App\\Untracked", None, &[20]),
+            file("App\\Cart", Some(5), &[5]),
+        ];
+
+        let ranking = hotspots(&files)
+            .into_iter()
+            .map(|hotspot| (hotspot.class, hotspot.score))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("App\\Cart".to_string(), 25.0),
+                ("App\\Invoice".to_string(), 25.0),
+                ("App\\Mailer".to_string(), 18.0),
+            ],
+            ranking
+        );
+        assert_eq!(None, hotspot_score(&files[2]));
+    }
+}
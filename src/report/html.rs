@@ -0,0 +1,431 @@
+use std::{collections::HashMap, fmt::Write as _, fs, io, path::Path};
+
+use serde_json::json;
+
+use crate::{
+    report::{namespace_tree, xml::escape, Namespace},
+    ClassDependencyIndex, File,
+};
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 0; color: #222; }
+nav { background: #222; padding: 0.6em 1em; }
+nav a { color: #eee; margin-right: 1.5em; text-decoration: none; }
+main { padding: 1em 2em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.3em 0.6em; text-align: left; }
+th[data-sort] { cursor: pointer; user-select: none; }
+th[data-sort]:hover { background: #eee; }
+td.number { text-align: right; }
+.bad { color: #b00; font-weight: bold; }
+details { margin-left: 1em; }
+summary { cursor: pointer; }
+.stats { color: #666; font-size: 0.9em; }
+#graph { width: 100%; height: 80vh; border: 1px solid #ddd; }
+#graph line { stroke: #bbb; }
+#graph circle { fill: #4a7ab5; }
+#graph text { font-size: 10px; fill: #333; }
+"#;
+
+const SORT_SCRIPT: &str = r#"
+document.querySelectorAll("th[data-sort]").forEach(function (th) {
+  var column = th.cellIndex, descending = false;
+  th.addEventListener("click", function () {
+    var body = th.closest("table").tBodies[0];
+    var rows = Array.prototype.slice.call(body.rows);
+    var numeric = th.dataset.sort === "number";
+    descending = !descending;
+    rows.sort(function (a, b) {
+      var x = a.cells[column].dataset.value || a.cells[column].textContent;
+      var y = b.cells[column].dataset.value || b.cells[column].textContent;
+      var order = numeric ? parseFloat(x) - parseFloat(y) : x.localeCompare(y);
+      return descending ? -order : order;
+    });
+    rows.forEach(function (row) { body.appendChild(row); });
+  });
+});
+"#;
+
+// A small force directed layout so the graph needs no library
+const GRAPH_SCRIPT: &str = r#"
+var data = JSON.parse(document.getElementById("graph-data").textContent);
+var svg = document.getElementById("graph");
+var width = svg.clientWidth, height = svg.clientHeight;
+var nodes = data.nodes.map(function (node, i) {
+  var angle = 2 * Math.PI * i / data.nodes.length;
+  return { node: node, x: width / 2 + Math.cos(angle) * width / 3, y: height / 2 + Math.sin(angle) * height / 3, dx: 0, dy: 0 };
+});
+for (var step = 0; step < 300; step++) {
+  nodes.forEach(function (a) {
+    a.dx = (width / 2 - a.x) * 0.01;
+    a.dy = (height / 2 - a.y) * 0.01;
+    nodes.forEach(function (b) {
+      if (a === b) return;
+      var x = a.x - b.x, y = a.y - b.y, distance = Math.max(Math.sqrt(x * x + y * y), 1);
+      a.dx += x / distance * 800 / distance;
+      a.dy += y / distance * 800 / distance;
+    });
+  });
+  data.edges.forEach(function (edge) {
+    var a = nodes[edge[0]], b = nodes[edge[1]];
+    var x = b.x - a.x, y = b.y - a.y;
+    a.dx += x * 0.02; a.dy += y * 0.02;
+    b.dx -= x * 0.02; b.dy -= y * 0.02;
+  });
+  nodes.forEach(function (node) {
+    node.x = Math.min(width - 10, Math.max(10, node.x + Math.max(-10, Math.min(10, node.dx))));
+    node.y = Math.min(height - 10, Math.max(10, node.y + Math.max(-10, Math.min(10, node.dy))));
+  });
+}
+function element(name, attributes, parent) {
+  var el = document.createElementNS("http://www.w3.org/2000/svg", name);
+  Object.keys(attributes).forEach(function (key) { el.setAttribute(key, attributes[key]); });
+  parent.appendChild(el);
+  return el;
+}
+data.edges.forEach(function (edge) {
+  var a = nodes[edge[0]], b = nodes[edge[1]];
+  element("line", { x1: a.x, y1: a.y, x2: b.x, y2: b.y }, svg);
+});
+nodes.forEach(function (node) {
+  var link = element("a", { href: node.node.page }, svg);
+  var circle = element("circle", { cx: node.x, cy: node.y, r: 4 + Math.min(node.node.complexity, 12) }, link);
+  element("title", {}, circle).textContent = node.node.name;
+  element("text", { x: node.x + 8, y: node.y + 3 }, link).textContent = node.node.label;
+});
+"#;
+
+/// Writes a static HTML report into `dir`: a sortable class table, a page per class, the
+/// namespace tree and a dependency graph. Everything is inlined or local, so it works
+/// offline.
+pub fn html(dir: &Path, files: &[File], index: &ClassDependencyIndex) -> io::Result<()> {
+    fs::create_dir_all(dir.join("classes"))?;
+    fs::write(dir.join("style.css"), STYLE)?;
+    fs::write(dir.join("sort.js"), SORT_SCRIPT)?;
+    fs::write(dir.join("graph.js"), GRAPH_SCRIPT)?;
+
+    let pages = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| (file.class.name.trim_start_matches('\\'), i))
+        .collect::<HashMap<_, _>>();
+
+    fs::write(dir.join("index.html"), class_table(files, index))?;
+    fs::write(dir.join("namespaces.html"), namespaces(files, &pages))?;
+    fs::write(dir.join("graph.html"), graph(files, index, &pages))?;
+    let dependents = dependents(files);
+    for (i, file) in files.iter().enumerate() {
+        let page = class_page(file, index, &pages, &dependents);
+        fs::write(dir.join("classes").join(format!("{i}.html")), page)?;
+    }
+    Ok(())
+}
+
+/// The classes depending on each class, by name without the leading backslash.
+fn dependents(files: &[File]) -> HashMap<&str, Vec<&str>> {
+    let mut dependents = HashMap::<&str, Vec<&str>>::new();
+    for file in files {
+        for dependency in file.class.dependencies.iter() {
+            dependents
+                .entry(dependency.trim_start_matches('\\'))
+                .or_default()
+                .push(file.class.name.as_str());
+        }
+    }
+    dependents
+}
+
+fn page(title: &str, root: &str, body: &str, scripts: &[&str]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{} - tcplscan</title>\n\
+         <link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n<body>\n<nav>\
+         <a href=\"{root}index.html\">Classes</a><a href=\"{root}namespaces.html\">Namespaces</a>\
+         <a href=\"{root}graph.html\">Dependency graph</a></nav>\n<main>\n<h1>{}</h1>\n{body}</main>\n",
+        escape(title),
+        escape(title)
+    );
+    for script in scripts {
+        let _ = writeln!(html, "<script src=\"{root}{script}\"></script>");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn class_link(pages: &HashMap<&str, usize>, class: &str, root: &str) -> String {
+    match pages.get(class.trim_start_matches('\\')) {
+        Some(i) => format!("<a href=\"{root}classes/{i}.html\">{}</a>", escape(class)),
+        None => escape(class),
+    }
+}
+
+fn number(value: impl std::fmt::Display) -> String {
+    format!("<td class=\"number\">{value}</td>")
+}
+
+fn class_table(files: &[File], index: &ClassDependencyIndex) -> String {
+    let mut body = format!("<p>{} classes</p>\n<table>\n<thead><tr>", files.len());
+    let headers = [
+        ("Class", "text"),
+        ("Lines", "number"),
+        ("Uses", "number"),
+        ("Dependencies", "number"),
+        ("Functions", "number"),
+        ("Average complexity", "number"),
+        ("Max complexity", "number"),
+        ("Extends", "text"),
+        ("Implements", "number"),
+        ("Abstract", "text"),
+        ("Commits", "number"),
+    ];
+    for (header, kind) in headers {
+        let _ = write!(body, "<th data-sort=\"{kind}\">{header}</th>");
+    }
+    body.push_str("</tr></thead>\n<tbody>\n");
+    for (i, file) in files.iter().enumerate() {
+        let class = &file.class;
+        let _ = writeln!(
+            body,
+            "<tr><td><a href=\"classes/{i}.html\">{}</a></td>{}{}{}{}{}{}<td>{}</td>{}<td>{}</td>{}</tr>",
+            escape(&class.name),
+            number(file.lines),
+            number(index.get(&class.name).unwrap_or(&0)),
+            number(class.dependencies.len()),
+            number(class.functions.len()),
+            number(format!("{:.2}", class.average_complexity())),
+            number(class.highest_complexity_function()),
+            escape(class.extends.as_deref().unwrap_or("")),
+            number(class.implements.len()),
+            class.is_abstract,
+            number(file.history.as_ref().map_or(0, |history| history.commits)),
+        );
+    }
+    body.push_str("</tbody>\n</table>\n");
+    page("Classes", "", &body, &["sort.js"])
+}
+
+fn class_page(
+    file: &File,
+    index: &ClassDependencyIndex,
+    pages: &HashMap<&str, usize>,
+    dependents: &HashMap<&str, Vec<&str>>,
+) -> String {
+    let class = &file.class;
+    let root = "../";
+    let mut body = String::from("<table>\n");
+    let mut row = |name: &str, value: String| {
+        let _ = writeln!(body, "<tr><th>{name}</th><td>{value}</td></tr>");
+    };
+    row("Path", escape(&file.path));
+    row("Lines", file.lines.to_string());
+    row(
+        "Used in",
+        format!("{} places", index.get(&class.name).unwrap_or(&0)),
+    );
+    row(
+        "Average cyclomatic complexity",
+        format!("{:.2}", class.average_complexity()),
+    );
+    row(
+        "Max cyclomatic complexity",
+        class.highest_complexity_function().to_string(),
+    );
+    row(
+        "Extends",
+        class
+            .extends
+            .as_deref()
+            .map_or("None".to_string(), |parent| class_link(pages, parent, root)),
+    );
+    row("Abstract", class.is_abstract.to_string());
+    if let Some(history) = &file.history {
+        row(
+            "Commits",
+            format!("{} by {} authors", history.commits, history.authors),
+        );
+    }
+    if let Some(expected) = &file.autoload_mismatch {
+        row(
+            "Autoload mismatch",
+            format!("<span class=\"bad\">expected {}</span>", escape(expected)),
+        );
+    }
+    body.push_str("</table>\n");
+
+    let list = |title: &str, classes: Vec<&str>| {
+        let mut html = format!("<h2>{title} ({})</h2>\n<ul>\n", classes.len());
+        for class in classes {
+            let _ = writeln!(html, "<li>{}</li>", class_link(pages, class, root));
+        }
+        html.push_str("</ul>\n");
+        html
+    };
+    body.push_str(&list(
+        "Implements",
+        class.implements.iter().map(String::as_str).collect(),
+    ));
+    body.push_str(&list(
+        "Dependencies",
+        class.dependencies.iter().map(String::as_str).collect(),
+    ));
+    let dependents = dependents
+        .get(class.name.trim_start_matches('\\'))
+        .cloned()
+        .unwrap_or_default();
+    body.push_str(&list("Dependents", dependents));
+
+    let _ = writeln!(body, "<h2>Functions ({})</h2>", class.functions.len());
+    body.push_str("<table>\n<thead><tr><th data-sort=\"text\">Name</th><th data-sort=\"number\">Line</th><th data-sort=\"text\">Visibility</th><th data-sort=\"number\">Params</th><th data-sort=\"text\">Return type</th><th data-sort=\"number\">Complexity</th><th>Statements</th></tr></thead>\n<tbody>\n");
+    for function in class.functions.iter() {
+        let stmts = function
+            .stmts
+            .iter()
+            .map(|stmt| escape(&stmt.to_string()))
+            .collect::<Vec<_>>()
+            .join("<br>");
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td>{}<td>{}</td>{}<td>{}</td>{}<td>{stmts}</td></tr>",
+            escape(&function.name),
+            number(function.line),
+            function.visibility,
+            number(function.params),
+            escape(function.return_type.as_deref().unwrap_or("")),
+            number(function.complexity()),
+        );
+    }
+    body.push_str("</tbody>\n</table>\n");
+    page(&class.name, root, &body, &["sort.js"])
+}
+
+fn namespaces(files: &[File], pages: &HashMap<&str, usize>) -> String {
+    fn tree(namespace: &Namespace, pages: &HashMap<&str, usize>, html: &mut String) {
+        let stats = &namespace.stats;
+        let name = if namespace.name.is_empty() {
+            "Project"
+        } else {
+            namespace.name.as_str()
+        };
+        let _ = writeln!(
+            html,
+            "<details open><summary>{} <span class=\"stats\">{} classes, {} lines, \
+             average complexity {:.2}, max complexity {}, dependencies {} inbound {} outbound</span></summary>",
+            escape(name),
+            stats.classes,
+            stats.lines,
+            stats.average_complexity(),
+            stats.max_function_complexity,
+            stats.inbound,
+            stats.outbound
+        );
+        for child in namespace.children.iter() {
+            tree(child, pages, html);
+        }
+        if !namespace.classes.is_empty() {
+            html.push_str("<ul>\n");
+            for class in namespace.classes.iter() {
+                let _ = writeln!(html, "<li>{}</li>", class_link(pages, class, ""));
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</details>\n");
+    }
+    let mut body = String::new();
+    tree(&namespace_tree(files), pages, &mut body);
+    page("Namespaces", "", &body, &[])
+}
+
+/// The layout compares every pair of nodes on each step, so bigger graphs get too slow
+/// to render in a browser.
+const GRAPH_NODES: usize = 150;
+
+/// The dependencies between the most used classes, laid out in the browser.
+fn graph(files: &[File], index: &ClassDependencyIndex, pages: &HashMap<&str, usize>) -> String {
+    let uses = |file: &File| index.get(&file.class.name).copied().unwrap_or(0);
+    let mut shown = (0..files.len()).collect::<Vec<_>>();
+    shown.sort_by_key(|i| std::cmp::Reverse(uses(&files[*i])));
+    shown.truncate(GRAPH_NODES);
+    shown.sort();
+    // Edges refer to positions in the node list, not in `files`
+    let positions = shown
+        .iter()
+        .enumerate()
+        .map(|(position, i)| (*i, position))
+        .collect::<HashMap<_, _>>();
+
+    let nodes = shown
+        .iter()
+        .map(|i| {
+            let name = files[*i].class.name.trim_start_matches('\\');
+            json!({
+                "name": name,
+                "label": name.rsplit('\\').next().unwrap_or(name),
+                "page": format!("classes/{i}.html"),
+                "complexity": files[*i].class.highest_complexity_function(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let edges = shown
+        .iter()
+        .flat_map(|i| {
+            let from = positions[i];
+            files[*i]
+                .class
+                .dependencies
+                .iter()
+                .filter_map(|dependency| pages.get(dependency.trim_start_matches('\\')))
+                .filter_map(|target| positions.get(target))
+                .filter(move |to| **to != from)
+                .map(move |to| json!([from, to]))
+        })
+        .collect::<Vec<_>>();
+    let data = json!({ "nodes": nodes, "edges": edges })
+        .to_string()
+        .replace("</", "<\\/");
+    let shown = if files.len() > GRAPH_NODES {
+        format!(
+            "the {GRAPH_NODES} most used of {} classes. The graph is limited to {GRAPH_NODES} \
+             classes to stay responsive, the others are left out along with their dependencies",
+            files.len()
+        )
+    } else {
+        format!("the scanned classes, at most the {GRAPH_NODES} most used are shown")
+    };
+    let body = format!(
+        "<p>Dependencies between {shown}. Bigger nodes have more complex functions.</p>\n\
+         <svg id=\"graph\"></svg>\n<script type=\"application/json\" id=\"graph-data\">{data}</script>\n"
+    );
+    page("Dependency graph", "", &body, &["graph.js"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html() {
+        let mut invoice = File::test("App\\Billing\\Invoice", &["App\\Billing\\Money"]);
+        invoice.class.extends = Some("App\\Model<T>".to_string());
+        let files = [invoice, File::test("App\\Billing\\Money", &[])];
+        let index = ClassDependencyIndex::from([("App\\Billing\\Money".to_string(), 1)]);
+        let dir = std::env::temp_dir().join(format!("tcplscan-html-{}", std::process::id()));
+        html(&dir, &files, &index).unwrap();
+        let read = |path: &str| fs::read_to_string(dir.join(path)).unwrap();
+
+        let table = read("index.html");
+        assert!(table.contains("<p>2 classes</p>"));
+        assert!(table.contains("<a href=\"classes/0.html\">App\\Billing\\Invoice</a>"));
+        let invoice = read("classes/0.html");
+        assert!(invoice.contains("<th>Extends</th><td>App\\Model&lt;T&gt;</td>"));
+        assert!(invoice.contains("<h2>Dependencies (1)</h2>"));
+        assert!(invoice.contains("<a href=\"../classes/1.html\">App\\Billing\\Money</a>"));
+        let money = read("classes/1.html");
+        assert!(money.contains("<h2>Dependents (1)</h2>"));
+        assert!(money.contains("<th>Used in</th><td>1 places</td>"));
+        let graph = read("graph.html");
+        assert!(graph.contains("at most the 150 most used are shown"));
+        assert!(graph.contains("[[0,1]]"));
+        assert!(read("namespaces.html").contains("App\\Billing"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,28 @@
+pub use self::baseline::Baseline;
+pub use self::diff::{diff, ScanDiff};
+pub use self::functions::{rank_functions, FunctionMetric, RankedFunction};
+pub use self::hotspots::{hotspot_score, hotspots};
+pub use self::html::html;
+pub use self::namespaces::{namespace_tree, Namespace};
+pub use self::packages::package_coupling;
+pub use self::profile::{find_class, profile, Profile};
+pub use self::sarif::sarif;
+pub use self::tables::{class_table, function_table};
+pub use self::trend::{trend_csv, trend_point, TrendPoint};
+pub use self::violations::{violations, Violation};
+pub use self::xml::{checkstyle, junit};
+
+mod baseline;
+mod cycles;
+mod diff;
+mod functions;
+mod hotspots;
+mod html;
+mod namespaces;
+mod packages;
+mod profile;
+mod sarif;
+mod tables;
+mod trend;
+mod violations;
+mod xml;
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::File;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct NamespaceStats {
+    pub classes: usize,
+    pub functions: usize,
+    pub total_complexity: usize,
+    pub max_function_complexity: usize,
+    pub lines: usize,
+    /// Dependencies of all classes in the namespace
+    pub dependencies: usize,
+    /// Dependencies from classes outside the namespace on classes inside it
+    pub inbound: usize,
+    /// Dependencies from classes inside the namespace on classes outside it
+    pub outbound: usize,
+}
+
+impl NamespaceStats {
+    pub fn average_complexity(&self) -> f64 {
+        if self.functions == 0 {
+            return 0.0;
+        }
+        self.total_complexity as f64 / self.functions as f64
+    }
+
+    pub fn average_dependencies(&self) -> f64 {
+        if self.classes == 0 {
+            return 0.0;
+        }
+        self.dependencies as f64 / self.classes as f64
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Namespace {
+    pub name: String,
+    pub stats: NamespaceStats,
+    pub children: Vec<Namespace>,
+    /// Classes declared directly in this namespace
+    pub classes: Vec<String>,
+}
+
+/// Rolls class metrics up into every namespace prefix. The root holds the whole project.
+pub fn namespace_tree(files: &[File]) -> Namespace {
+    let mut stats: BTreeMap<String, NamespaceStats> = BTreeMap::new();
+    stats.insert(String::new(), NamespaceStats::default());
+
+    for file in files.iter() {
+        let class = &file.class;
+        let namespace = namespace_of(&class.name);
+        for prefix in prefixes(namespace) {
+            let entry = stats.entry(prefix.to_owned()).or_default();
+            entry.classes += 1;
+            entry.lines += file.lines;
+            entry.dependencies += class.dependencies.len();
+            entry.functions += class.functions.len();
+            for function in class.functions.iter() {
+                entry.total_complexity += function.complexity();
+            }
+            entry.max_function_complexity = entry
+                .max_function_complexity
+                .max(class.highest_complexity_function());
+        }
+    }
+
+    for file in files.iter() {
+        let namespace = namespace_of(&file.class.name);
+        for dependency in file.class.dependencies.iter() {
+            let target = namespace_of(dependency);
+            for prefix in prefixes(namespace) {
+                if !contains(prefix, target) {
+                    stats.get_mut(prefix).unwrap().outbound += 1;
+                }
+            }
+            for prefix in prefixes(target) {
+                if contains(prefix, namespace) {
+                    continue;
+                }
+                if let Some(entry) = stats.get_mut(prefix) {
+                    entry.inbound += 1;
+                }
+            }
+        }
+    }
+
+    // Group by parent once, looking them up per namespace is quadratic in big projects
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    for key in stats.keys().filter(|key| !key.is_empty()) {
+        children.entry(parent_of(key)).or_default().push(key);
+    }
+    let mut classes: HashMap<&str, Vec<String>> = HashMap::new();
+    for file in files.iter() {
+        classes
+            .entry(namespace_of(&file.class.name))
+            .or_default()
+            .push(file.class.name.to_owned());
+    }
+    build("", &stats, &children, &mut classes)
+}
+
+fn build(
+    name: &str,
+    stats: &BTreeMap<String, NamespaceStats>,
+    children: &HashMap<&str, Vec<&str>>,
+    classes: &mut HashMap<&str, Vec<String>>,
+) -> Namespace {
+    let children = children
+        .get(name)
+        .map_or(&[][..], Vec::as_slice)
+        .iter()
+        .map(|child| build(child, stats, children, classes))
+        .collect();
+    let mut classes = classes.remove(name).unwrap_or_default();
+    classes.sort();
+    Namespace {
+        name: name.to_owned(),
+        stats: stats[name].clone(),
+        children,
+        classes,
+    }
+}
+
+fn namespace_of(class_name: &str) -> &str {
+    let class_name = class_name.trim_start_matches('\\');
+    match class_name.rsplit_once('\\') {
+        Some((namespace, _)) => namespace,
+        None => "",
+    }
+}
+
+fn parent_of(namespace: &str) -> &str {
+    match namespace.rsplit_once('\\') {
+        Some((parent, _)) => parent,
+        None => "",
+    }
+}
+
+/// The root followed by every prefix of a namespace, e.g. "", "App", "App\Billing".
+fn prefixes(namespace: &str) -> Vec<&str> {
+    let mut prefixes = vec![""];
+    if namespace.is_empty() {
+        return prefixes;
+    }
+    for (i, c) in namespace.char_indices() {
+        if c == '\\' {
+            prefixes.push(&namespace[..i]);
+        }
+    }
+    prefixes.push(namespace);
+    prefixes
+}
+
+fn contains(prefix: &str, namespace: &str) -> bool {
+    prefix.is_empty()
+        || namespace == prefix
+        || namespace
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('\\'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefixes() {
+        assert_eq!(vec![""], prefixes(""));
+        assert_eq!(
+            vec!["", "App", "App\\Billing", "App\\Billing\\Tax"],
+            prefixes("App\\Billing\\Tax")
+        );
+        assert!(contains("App", "App\\Billing"));
+        assert!(!contains("App", "Application"));
+    }
+
+    #[test]
+    fn test_namespace_tree() {
+        let files = [
+            File::test(
+                "App\\Billing\\Invoice",
+                &["App\\Billing\\Money", "\\App\\Shared\\Clock"],
+            ),
+            File::test("App\\Billing\\Money", &[]),
+            File::test("App\\Shared\\Clock", &[]),
+            File::test(
+                "App\\Http\\InvoiceController",
+                &["App\\Billing\\Invoice", "Psr\\Log\\LoggerInterface"],
+            ),
+        ];
+        let root = namespace_tree(&files);
+        assert_eq!(4, root.stats.classes);
+        assert_eq!(40, root.stats.lines);
+        assert_eq!(vec!["App"], names(&root.children));
+
+        let app = &root.children[0];
+        assert_eq!(
+            vec!["App\\Billing", "App\\Http", "App\\Shared"],
+            names(&app.children)
+        );
+        assert_eq!(
+            (4, 0, 1),
+            (
+                app.stats.dependencies,
+                app.stats.inbound,
+                app.stats.outbound
+            )
+        );
+
+        let billing = &app.children[0];
+        assert_eq!(
+            vec!["App\\Billing\\Invoice", "App\\Billing\\Money"],
+            billing.classes
+        );
+        assert_eq!((2, 20), (billing.stats.classes, billing.stats.lines));
+        assert_eq!((1, 1), (billing.stats.inbound, billing.stats.outbound));
+        let shared = &app.children[2];
+        assert_eq!((1, 0), (shared.stats.inbound, shared.stats.outbound));
+    }
+
+    fn names(namespaces: &[Namespace]) -> Vec<&str> {
+        namespaces
+            .iter()
+            .map(|namespace| namespace.name.as_str())
+            .collect()
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{Composer, File};
+
+/// How much of our code depends on a single third-party package.
+#[derive(Debug, PartialEq)]
+pub struct PackageUsage {
+    pub package: String,
+    /// Our classes using the package along with how many of its classes they reference
+    pub classes: Vec<(String, usize)>,
+    pub references: usize,
+    /// Distinct classes of the package we reference
+    pub dependencies: Vec<String>,
+}
+
+pub fn package_coupling(files: &[File], composer: Option<&Composer>) -> Vec<PackageUsage> {
+    let own_classes = files
+        .iter()
+        .map(|file| file.class.name.trim_start_matches('\\'))
+        .collect::<HashSet<_>>();
+    let own_namespaces = own_classes
+        .iter()
+        .filter_map(|name| name.rsplit_once('\\').map(|(namespace, _)| namespace))
+        .collect::<HashSet<_>>();
+
+    let mut packages: BTreeMap<String, PackageUsage> = BTreeMap::new();
+    for file in files.iter() {
+        let class = &file.class;
+        let mut references: BTreeMap<String, usize> = BTreeMap::new();
+        for dependency in class.dependencies.iter() {
+            let dependency = dependency.trim_start_matches('\\');
+            if own_classes.contains(dependency) || in_namespaces(dependency, &own_namespaces) {
+                continue;
+            }
+            let package = match owning_package(dependency, composer) {
+                Some(package) => package,
+                None => continue,
+            };
+            *references.entry(package.clone()).or_insert(0) += 1;
+            let usage = packages
+                .entry(package)
+                .or_insert_with_key(|package| PackageUsage {
+                    package: package.to_owned(),
+                    classes: Vec::new(),
+                    references: 0,
+                    dependencies: Vec::new(),
+                });
+            if !usage.dependencies.iter().any(|d| d == dependency) {
+                usage.dependencies.push(dependency.to_owned());
+            }
+        }
+        for (package, count) in references {
+            let usage = packages.get_mut(&package).unwrap();
+            usage.classes.push((class.name.to_owned(), count));
+            usage.references += count;
+        }
+    }
+
+    let mut packages = packages.into_values().collect::<Vec<_>>();
+    for usage in packages.iter_mut() {
+        usage
+            .classes
+            .sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        usage.dependencies.sort();
+    }
+    packages.sort_by_key(|usage| std::cmp::Reverse((usage.classes.len(), usage.references)));
+    packages
+}
+
+/// Whether the class is in one of the namespaces or below it.
+fn in_namespaces(class_name: &str, namespaces: &HashSet<&str>) -> bool {
+    class_name
+        .match_indices('\\')
+        .any(|(i, _)| namespaces.contains(&class_name[..i]))
+}
+
+/// Resolves the package through composer metadata, falling back to the vendor, the first
+/// segment of the name. Our own package and the global namespace are skipped.
+fn owning_package(dependency: &str, composer: Option<&Composer>) -> Option<String> {
+    if let Some(composer) = composer {
+        if let Some(package) = composer.package_for(dependency) {
+            if package == composer.name {
+                return None;
+            }
+            return Some(package.to_owned());
+        }
+    }
+    let (vendor, _) = dependency.split_once('\\')?;
+    Some(vendor.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owning_package_without_composer() {
+        assert_eq!(
+            Some("Doctrine".to_string()),
+            owning_package("Doctrine\\ORM\\Mapping\\Entity", None)
+        );
+        assert_eq!(
+            Some("Monolog".to_string()),
+            owning_package("Monolog\\Handler\\StreamHandler", None)
+        );
+        assert_eq!(
+            Some("Monolog".to_string()),
+            owning_package("Monolog\\Logger", None)
+        );
+        assert_eq!(
+            None,
+            owning_package("\\DateTime".trim_start_matches('\\'), None)
+        );
+    }
+
+    #[test]
+    fn test_own_namespaces_are_first_party() {
+        let files = vec![File::test(
+            "App\\Billing\\Invoice",
+            &[
+                "App\\Billing\\Contracts\\Payable",
+                "App\\Billing\\Money",
+                "Monolog\\Logger",
+                "Monolog\\Handler\\StreamHandler",
+                "Doctrine\\ORM\\Mapping\\Entity",
+            ],
+        )];
+        let packages = package_coupling(&files, None)
+            .into_iter()
+            .map(|usage| usage.package)
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Monolog", "Doctrine"], packages);
+    }
+}
@@ -0,0 +1,128 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::{ClassDependencyIndex, File};
+
+/// Everything known about one class and how it relates to the rest of the scan.
+#[derive(Debug, Serialize)]
+pub struct Profile<'a> {
+    #[serde(flatten)]
+    pub file: &'a File,
+    pub usages: usize,
+    pub average_complexity: f64,
+    pub max_complexity: usize,
+    /// The parent class, its parent and so on, as far as they are part of the scan
+    pub parents: Vec<String>,
+    /// Classes extending this one directly
+    pub children: Vec<String>,
+    /// Classes depending on this one
+    pub dependents: Vec<String>,
+}
+
+/// Finds the class with exactly this fully qualified name, or the file at this path.
+pub fn find_class<'a>(files: &'a [File], query: &str) -> Option<&'a File> {
+    if let Some(file) = named(files, query) {
+        return Some(file);
+    }
+    let path = Path::new(query).canonicalize().ok()?;
+    files.iter().find(|file| {
+        Path::new(&file.path)
+            .canonicalize()
+            .is_ok_and(|other| other == path)
+    })
+}
+
+/// PHP class names are case insensitive.
+fn named<'a>(files: &'a [File], name: &str) -> Option<&'a File> {
+    files.iter().find(|file| same_name(&file.class.name, name))
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim_start_matches('\\')
+        .eq_ignore_ascii_case(b.trim_start_matches('\\'))
+}
+
+pub fn profile<'a>(file: &'a File, files: &[File], index: &ClassDependencyIndex) -> Profile<'a> {
+    let class = &file.class;
+    let is = |other: &String| same_name(other, &class.name);
+
+    let mut parents = Vec::new();
+    let mut parent = class.extends.clone();
+    while let Some(name) = parent {
+        // An inheritance loop can't be valid PHP, but don't follow one forever
+        if parents.contains(&name) || same_name(&name, &class.name) {
+            break;
+        }
+        parent = named(files, &name).and_then(|file| file.class.extends.clone());
+        parents.push(name);
+    }
+
+    let mut children = files
+        .iter()
+        .filter(|other| other.class.extends.as_ref().is_some_and(is))
+        .map(|other| other.class.name.to_owned())
+        .collect::<Vec<_>>();
+    children.sort();
+    let mut dependents = files
+        .iter()
+        .filter(|other| other.class.dependencies.iter().any(is))
+        .map(|other| other.class.name.to_owned())
+        .collect::<Vec<_>>();
+    dependents.sort();
+
+    Profile {
+        file,
+        usages: index.get(&class.name).copied().unwrap_or(0),
+        average_complexity: class.average_complexity(),
+        max_complexity: class.highest_complexity_function(),
+        parents,
+        children,
+        dependents,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_name() {
+        assert!(same_name(
+            "App\\Billing\\Invoice",
+            "\\app\\billing\\invoice"
+        ));
+        assert!(!same_name("App\\Billing\\Invoice", "Invoice"));
+    }
+
+    #[test]
+    fn test_profile() {
+        let file = |name: &str, extends: Option<&str>, dependencies: &[&str]| {
+            let mut file = File::test(name, dependencies);
+            file.class.extends = extends.map(str::to_string);
+            file
+        };
+        let files = vec![
+            file("App\\Model", None, &[]),
+            file("App\\Invoice", Some("App\\Model"), &["App\\Model"]),
+            file("App\\CreditNote", Some("App\\Invoice"), &["\\App\\Invoice"]),
+        ];
+        let index = ClassDependencyIndex::from([("App\\Invoice".to_string(), 1)]);
+
+        let invoice = profile(&files[1], &files, &index);
+        assert_eq!(vec!["App\\Model"], invoice.parents);
+        assert_eq!(vec!["App\\CreditNote"], invoice.children);
+        assert_eq!(vec!["App\\CreditNote"], invoice.dependents);
+        assert_eq!(1, invoice.usages);
+
+        let credit_note = profile(&files[2], &files, &index);
+        assert_eq!(vec!["App\\Invoice", "App\\Model"], credit_note.parents);
+        assert!(credit_note.children.is_empty());
+        assert!(credit_note.dependents.is_empty());
+
+        let model = profile(&files[0], &files, &index);
+        assert!(model.parents.is_empty());
+        assert_eq!(vec!["App\\Invoice"], model.children);
+        assert_eq!(vec!["App\\Invoice"], model.dependents);
+        assert_eq!(0, model.usages);
+    }
+}
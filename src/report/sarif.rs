@@ -0,0 +1,137 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use crate::report::violations::{Rule, Violation};
+
+const RULES: [Rule; 6] = [
+    Rule::FunctionComplexity,
+    Rule::AverageComplexity,
+    Rule::Dependencies,
+    Rule::Lines,
+    Rule::Layer,
+    Rule::Cycle,
+];
+
+/// A SARIF 2.1.0 log of the violations, with paths relative to `root`.
+pub fn sarif(violations: &[Violation], root: &str) -> Value {
+    let rules = RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.to_string(),
+                "shortDescription": { "text": rule.description() },
+            })
+        })
+        .collect::<Vec<_>>();
+    let results = violations
+        .iter()
+        .map(|violation| {
+            let uri = uri(&violation.path, root);
+            let related = violation
+                .related_lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    json!({
+                        "id": i,
+                        "physicalLocation": location(&uri, *line),
+                        "message": { "text": "Branch adding to the complexity" },
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "ruleId": violation.rule.to_string(),
+                "ruleIndex": RULES.iter().position(|rule| *rule == violation.rule),
+                "level": "error",
+                "message": { "text": violation.message },
+                "locations": [{ "physicalLocation": location(&uri, violation.line) }],
+                "relatedLocations": related,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "tcplscan",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                }
+            },
+            "originalUriBaseIds": {
+                "SRCROOT": { "uri": root_uri(root) },
+            },
+            "results": results,
+        }]
+    })
+}
+
+fn location(uri: &Value, line: usize) -> Value {
+    json!({
+        "artifactLocation": uri,
+        // SARIF lines start at 1, a line we don't know points at the top of the file
+        "region": { "startLine": line.max(1) },
+    })
+}
+
+fn root_uri(root: &str) -> String {
+    let root = fs::canonicalize(root).unwrap_or_else(|_| PathBuf::from(root));
+    let root = encode(&root.to_string_lossy());
+    format!("file://{}/", root.trim_end_matches('/'))
+}
+
+/// Paths below the scanned directory are relative to it, anything else is absolute.
+fn uri(path: &str, root: &str) -> Value {
+    let path = Path::new(path);
+    match path.strip_prefix(root) {
+        Ok(relative) => json!({
+            "uri": encode(&relative.to_string_lossy()),
+            "uriBaseId": "SRCROOT",
+        }),
+        Err(_) => json!({ "uri": encode(&path.to_string_lossy()) }),
+    }
+}
+
+/// Turns a path into a URI path, percent-encoding every byte of a segment that isn't an
+/// unreserved URI character.
+fn encode(path: &str) -> String {
+    let mut uri = String::new();
+    for byte in path.replace('\\', "/").bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri() {
+        assert_eq!(
+            json!({ "uri": "src/Billing/Invoice.php", "uriBaseId": "SRCROOT" }),
+            uri("./src/Billing/Invoice.php", ".")
+        );
+        assert_eq!(
+            json!({ "uri": "/elsewhere/Invoice.php" }),
+            uri("/elsewhere/Invoice.php", "/project")
+        );
+        assert_eq!(
+            json!({ "uri": "src/My%20Code/%23Tax%25/Caf%C3%A9.php", "uriBaseId": "SRCROOT" }),
+            uri("/project/src/My Code/#Tax%/Café.php", "/project")
+        );
+    }
+}
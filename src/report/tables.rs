@@ -0,0 +1,136 @@
+use crate::{ClassDependencyIndex, File};
+
+use super::RankedFunction;
+
+/// A flat table of the scan, rendered as CSV or Markdown.
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+/// One row per class.
+pub fn class_table(files: &[File], index: &ClassDependencyIndex) -> Table {
+    let rows = files
+        .iter()
+        .map(|file| {
+            let class = &file.class;
+            vec![
+                class.name.to_owned(),
+                file.path.to_owned(),
+                file.lines.to_string(),
+                index.get(&class.name).unwrap_or(&0).to_string(),
+                class.dependencies.len().to_string(),
+                class.functions.len().to_string(),
+                format!("{:.2}", class.average_complexity()),
+                class.highest_complexity_function().to_string(),
+                class.extends.to_owned().unwrap_or_default(),
+                class.is_abstract.to_string(),
+            ]
+        })
+        .collect();
+    Table {
+        headers: vec![
+            "class",
+            "path",
+            "lines",
+            "usages",
+            "dependencies",
+            "functions",
+            "average_complexity",
+            "max_complexity",
+            "extends",
+            "abstract",
+        ],
+        rows,
+    }
+}
+
+/// One row per function, in the order given.
+pub fn function_table(functions: &[RankedFunction]) -> Table {
+    let rows = functions
+        .iter()
+        .map(|function| {
+            vec![
+                function.class.to_owned(),
+                function.function.to_owned(),
+                function.path.to_owned(),
+                function.line.to_string(),
+                function.visibility.to_owned(),
+                function.params.to_string(),
+                function.return_type.to_owned().unwrap_or_default(),
+                function.complexity.to_string(),
+                function.length.to_string(),
+                function.nesting.to_string(),
+            ]
+        })
+        .collect();
+    Table {
+        headers: vec![
+            "class",
+            "function",
+            "path",
+            "line",
+            "visibility",
+            "params",
+            "return_type",
+            "complexity",
+            "length",
+            "nesting",
+        ],
+        rows,
+    }
+}
+
+impl Table {
+    pub fn to_csv(&self) -> String {
+        let line = |cells: &mut dyn Iterator<Item = &str>| {
+            cells.map(csv_cell).collect::<Vec<_>>().join(",") + "\n"
+        };
+        let mut csv = line(&mut self.headers.iter().copied());
+        for row in self.rows.iter() {
+            csv.push_str(&line(&mut row.iter().map(String::as_str)));
+        }
+        csv
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let line = |cells: &mut dyn Iterator<Item = &str>| {
+            let cells = cells
+                .map(|cell| cell.replace('|', "\\|"))
+                .collect::<Vec<_>>();
+            format!("| {} |\n", cells.join(" | "))
+        };
+        let mut markdown = line(&mut self.headers.iter().copied());
+        markdown.push_str(&line(&mut self.headers.iter().map(|_| "---")));
+        for row in self.rows.iter() {
+            markdown.push_str(&line(&mut row.iter().map(String::as_str)));
+        }
+        markdown
+    }
+}
+
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let table = Table {
+            headers: vec!["class", "extends"],
+            rows: vec![vec!["App\\A".to_string(), "a,\"b\"|c".to_string()]],
+        };
+        assert_eq!("class,extends\nApp\\A,\"a,\"\"b\"\"|c\"\n", table.to_csv());
+        assert_eq!(
+            "| class | extends |\n| --- | --- |\n| App\\A | a,\"b\"\\|c |\n",
+            table.to_markdown()
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{report::namespace_tree, report::Namespace, File};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrendMetrics {
+    pub classes: usize,
+    pub total_complexity: usize,
+    pub average_dependencies: f64,
+}
+
+/// The metrics of one commit, for the whole project and every namespace.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendPoint {
+    pub commit: String,
+    pub date: String,
+    pub project: TrendMetrics,
+    pub namespaces: BTreeMap<String, TrendMetrics>,
+}
+
+pub fn trend_point(commit: &str, date: &str, files: &[File]) -> TrendPoint {
+    let root = namespace_tree(files);
+    let mut namespaces = BTreeMap::new();
+    let mut stack = root.children.iter().collect::<Vec<_>>();
+    while let Some(namespace) = stack.pop() {
+        namespaces.insert(namespace.name.to_owned(), metrics(namespace));
+        stack.extend(namespace.children.iter());
+    }
+    TrendPoint {
+        commit: commit.to_string(),
+        date: date.to_string(),
+        project: metrics(&root),
+        namespaces,
+    }
+}
+
+fn metrics(namespace: &Namespace) -> TrendMetrics {
+    TrendMetrics {
+        classes: namespace.stats.classes,
+        total_complexity: namespace.stats.total_complexity,
+        average_dependencies: namespace.stats.average_dependencies(),
+    }
+}
+
+/// One row per commit and namespace, the project-wide row has an empty namespace.
+pub fn trend_csv(points: &[TrendPoint]) -> String {
+    let mut csv =
+        String::from("commit,date,namespace,classes,total_complexity,average_dependencies\n");
+    for point in points.iter() {
+        let rows = std::iter::once(("", &point.project)).chain(
+            point
+                .namespaces
+                .iter()
+                .map(|(name, metrics)| (name.as_str(), metrics)),
+        );
+        for (namespace, metrics) in rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.2}\n",
+                point.commit,
+                point.date,
+                namespace,
+                metrics.classes,
+                metrics.total_complexity,
+                metrics.average_dependencies
+            ));
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexing::Function;
+
+    #[test]
+    fn test_trend_point() {
+        let file = |name: &str, dependencies: &[&str], complexities: &[usize]| {
+            let mut file = File::test(name, dependencies);
+            file.class.functions = complexities
+                .iter()
+                .map(|complexity| Function::test("run", *complexity))
+                .collect();
+            file
+        };
+        let files = [
+            file(
+                "App\\Billing\\Invoice",
+                &["App\\Billing\\Money", "App\\Tax"],
+                &[3, 2],
+            ),
+            file("App\\Billing\\Money", &[], &[1]),
+            file(
+                "App\\Reports\\Sales",
+                &["App\\Billing\\Invoice", "App\\Billing\\Money"],
+                &[4],
+            ),
+        ];
+        let point = trend_point("abc123", "2024-01-02", &files);
+
+        let metrics = |classes, total_complexity, average_dependencies| TrendMetrics {
+            classes,
+            total_complexity,
+            average_dependencies,
+        };
+        assert_eq!(metrics(3, 10, 4.0 / 3.0), point.project);
+        assert_eq!(
+            vec!["App", "App\\Billing", "App\\Reports"],
+            point.namespaces.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(metrics(3, 10, 4.0 / 3.0), point.namespaces["App"]);
+        assert_eq!(metrics(2, 6, 1.0), point.namespaces["App\\Billing"]);
+        assert_eq!(metrics(1, 4, 2.0), point.namespaces["App\\Reports"]);
+
+        let csv = trend_csv(&[point]);
+        assert_eq!(
+            vec![
+                "commit,date,namespace,classes,total_complexity,average_dependencies",
+                "abc123,2024-01-02,,3,10,1.33",
+                "abc123,2024-01-02,App,3,10,1.33",
+                "abc123,2024-01-02,App\\Billing,2,6,1.00",
+                "abc123,2024-01-02,App\\Reports,1,4,2.00",
+            ],
+            csv.lines().collect::<Vec<_>>()
+        );
+    }
+}
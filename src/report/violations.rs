@@ -0,0 +1,259 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Layer, Thresholds},
+    report::cycles::cycles,
+    File,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    FunctionComplexity,
+    AverageComplexity,
+    Dependencies,
+    Lines,
+    Layer,
+    Cycle,
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Rule::FunctionComplexity => "function-complexity",
+                Rule::AverageComplexity => "average-complexity",
+                Rule::Dependencies => "dependencies",
+                Rule::Lines => "lines",
+                Rule::Layer => "layer",
+                Rule::Cycle => "cycle",
+            }
+        )
+    }
+}
+
+impl Rule {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Rule::FunctionComplexity => "Function cyclomatic complexity is over the limit",
+            Rule::AverageComplexity => "Average cyclomatic complexity of a class is over the limit",
+            Rule::Dependencies => "Class has more dependencies than the limit",
+            Rule::Lines => "File has more lines than the limit",
+            Rule::Layer => "Class depends on a layer it isn't allowed to use",
+            Rule::Cycle => "Class is part of a dependency cycle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    pub rule: Rule,
+    pub class: String,
+    pub path: String,
+    /// The function for function level rules and the dependency for layer rules
+    pub subject: Option<String>,
+    pub value: f64,
+    pub limit: f64,
+    pub message: String,
+    /// The function declaration for function level rules, otherwise the class declaration
+    pub line: usize,
+    /// Lines that add to the value, like the branches of a complex function
+    pub related_lines: Vec<usize>,
+}
+
+/// Checks every class against the thresholds and layer rules.
+pub fn violations(files: &[File], thresholds: &Thresholds, layers: &[Layer]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for file in files.iter() {
+        let class = &file.class;
+        let violation = |rule, subject, value: f64, limit: f64, message| Violation {
+            rule,
+            class: class.name.to_owned(),
+            path: file.path.to_owned(),
+            subject,
+            value,
+            limit,
+            message,
+            line: class.line,
+            related_lines: Vec::new(),
+        };
+
+        if let Some(limit) = thresholds.function_complexity {
+            for function in class.functions.iter().filter(|f| file.is_changed(f)) {
+                let complexity = function.complexity();
+                if complexity > limit {
+                    violations.push(Violation {
+                        line: function.line,
+                        related_lines: function.branch_lines(),
+                        ..violation(
+                            Rule::FunctionComplexity,
+                            Some(function.name.to_owned()),
+                            complexity as f64,
+                            limit as f64,
+                            format!(
+                                "{}::{} has a cyclomatic complexity of {complexity}, the limit is {limit}",
+                                class.name, function.name
+                            ),
+                        )
+                    });
+                }
+            }
+        }
+        if let Some(limit) = thresholds.average_complexity {
+            let average = class.average_complexity();
+            if average > limit {
+                violations.push(violation(
+                    Rule::AverageComplexity,
+                    None,
+                    average,
+                    limit,
+                    format!(
+                        "{} has an average cyclomatic complexity of {average:.2}, the limit is {limit}",
+                        class.name
+                    ),
+                ));
+            }
+        }
+        if let Some(limit) = thresholds.dependencies {
+            let dependencies = class.dependencies.len();
+            if dependencies > limit {
+                violations.push(violation(
+                    Rule::Dependencies,
+                    None,
+                    dependencies as f64,
+                    limit as f64,
+                    format!(
+                        "{} has {dependencies} dependencies, the limit is {limit}",
+                        class.name
+                    ),
+                ));
+            }
+        }
+        if let Some(limit) = thresholds.lines {
+            if file.lines > limit {
+                violations.push(violation(
+                    Rule::Lines,
+                    None,
+                    file.lines as f64,
+                    limit as f64,
+                    format!(
+                        "{} has {} lines, the limit is {limit}",
+                        class.name, file.lines
+                    ),
+                ));
+            }
+        }
+
+        let own_layer = match layer_of(layers, &class.name) {
+            Some(layer) => layer,
+            None => continue,
+        };
+        for dependency in class.dependencies.iter() {
+            let layer = match layer_of(layers, dependency) {
+                Some(layer) => layer,
+                None => continue,
+            };
+            if layer.name == own_layer.name || own_layer.allow.contains(&layer.name) {
+                continue;
+            }
+            violations.push(violation(
+                Rule::Layer,
+                Some(dependency.to_owned()),
+                1.0,
+                0.0,
+                format!(
+                    "{} in layer {} depends on {} in layer {}",
+                    class.name, own_layer.name, dependency, layer.name
+                ),
+            ));
+        }
+    }
+
+    if thresholds.cycles {
+        for cycle in cycles(files) {
+            let members = cycle.iter().map(String::as_str).collect::<HashSet<_>>();
+            for file in files.iter() {
+                if !members.contains(file.class.name.trim_start_matches('\\')) {
+                    continue;
+                }
+                // Naming the whole cycle for every member grows with its size squared
+                let within = file
+                    .class
+                    .dependencies
+                    .iter()
+                    .map(|dependency| dependency.trim_start_matches('\\'))
+                    .filter(|dependency| members.contains(dependency))
+                    .collect::<Vec<_>>();
+                violations.push(Violation {
+                    rule: Rule::Cycle,
+                    class: file.class.name.to_owned(),
+                    path: file.path.to_owned(),
+                    subject: None,
+                    value: cycle.len() as f64,
+                    limit: 0.0,
+                    message: format!(
+                        "{} is part of a dependency cycle of {} classes through {}",
+                        file.class.name,
+                        cycle.len(),
+                        within.join(", ")
+                    ),
+                    line: file.class.line,
+                    related_lines: Vec::new(),
+                });
+            }
+        }
+    }
+    violations
+}
+
+/// The layer with the longest namespace containing the class.
+fn layer_of<'a>(layers: &'a [Layer], class_name: &str) -> Option<&'a Layer> {
+    let class_name = class_name.trim_start_matches('\\');
+    layers
+        .iter()
+        .filter(|layer| {
+            let namespace = layer.namespace.trim_matches('\\');
+            class_name
+                .strip_prefix(namespace)
+                .is_some_and(|rest| rest.starts_with('\\'))
+        })
+        .max_by_key(|layer| layer.namespace.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_messages() {
+        let files = [
+            File::test("App\\Invoice", &["App\\Customer", "App\\Money"]),
+            File::test("App\\Customer", &["\\App\\Order"]),
+            File::test("App\\Order", &["App\\Invoice", "App\\Customer"]),
+            File::test("App\\Money", &[]),
+        ];
+        let thresholds = Thresholds {
+            cycles: true,
+            ..Default::default()
+        };
+        let messages = violations(&files, &thresholds, &[])
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "App\\Invoice is part of a dependency cycle of 3 classes through App\\Customer",
+                "App\\Customer is part of a dependency cycle of 3 classes through App\\Order",
+                "App\\Order is part of a dependency cycle of 3 classes through App\\Invoice, App\\Customer",
+            ],
+            messages
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+};
+
+use crate::{report::violations::Violation, File};
+
+/// A Checkstyle report with one `<error>` per violation, grouped by file.
+pub fn checkstyle(violations: &[Violation]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<checkstyle version=\"{}\">",
+        env!("CARGO_PKG_VERSION")
+    );
+    let mut by_path: BTreeMap<&str, Vec<&Violation>> = BTreeMap::new();
+    for violation in violations.iter() {
+        by_path
+            .entry(violation.path.as_str())
+            .or_default()
+            .push(violation);
+    }
+    for (path, path_violations) in by_path {
+        let _ = writeln!(xml, "  <file name=\"{}\">", escape(path));
+        for violation in path_violations {
+            let _ = writeln!(
+                xml,
+                "    <error line=\"{}\" severity=\"error\" message=\"{}\" source=\"tcplscan.{}\"/>",
+                violation.line.max(1),
+                escape(&violation.message),
+                violation.rule
+            );
+        }
+        xml.push_str("  </file>\n");
+    }
+    xml.push_str("</checkstyle>\n");
+    xml
+}
+
+/// A JUnit report with a testcase per checked class that fails when the class has
+/// violations.
+pub fn junit(files: &[File], violations: &[Violation]) -> String {
+    let mut by_class: HashMap<(&str, &str), Vec<&Violation>> = HashMap::new();
+    for violation in violations.iter() {
+        by_class
+            .entry((violation.path.as_str(), violation.class.as_str()))
+            .or_default()
+            .push(violation);
+    }
+    let per_file = files
+        .iter()
+        .map(|file| {
+            by_class
+                .get(&(file.path.as_str(), file.class.name.as_str()))
+                .map_or(&[][..], Vec::as_slice)
+        })
+        .collect::<Vec<_>>();
+    let failures = per_file
+        .iter()
+        .filter(|violations| !violations.is_empty())
+        .count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"tcplscan\" tests=\"{}\" failures=\"{failures}\">",
+        files.len()
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"tcplscan\" tests=\"{}\" failures=\"{failures}\">",
+        files.len()
+    );
+    for (file, class_violations) in files.iter().zip(per_file) {
+        let class = &file.class.name;
+        let namespace = class
+            .rsplit_once('\\')
+            .map_or("", |(namespace, _)| namespace);
+        let _ = write!(
+            xml,
+            "    <testcase classname=\"{}\" name=\"{}\" file=\"{}\"",
+            escape(namespace.trim_start_matches('\\')),
+            escape(class.trim_start_matches('\\')),
+            escape(&file.path)
+        );
+        if class_violations.is_empty() {
+            xml.push_str("/>\n");
+            continue;
+        }
+        xml.push_str(">\n");
+        let message = match class_violations {
+            [violation] => violation.message.to_owned(),
+            _ => format!("{} violations", class_violations.len()),
+        };
+        let rules = class_violations
+            .iter()
+            .map(|violation| violation.rule.to_string())
+            .collect::<BTreeSet<_>>();
+        let _ = writeln!(
+            xml,
+            "      <failure message=\"{}\" type=\"{}\">",
+            escape(&message),
+            rules.into_iter().collect::<Vec<_>>().join(",")
+        );
+        for violation in class_violations {
+            let _ = writeln!(
+                xml,
+                "{}:{} {} [{}]",
+                escape(&file.path),
+                violation.line.max(1),
+                escape(&violation.message),
+                violation.rule
+            );
+        }
+        xml.push_str("      </failure>\n");
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n");
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::violations::Rule;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            "A&amp;B &lt;c&gt; &quot;d&quot; &apos;e&apos;",
+            escape("A&B <c> \"d\" 'e'")
+        );
+    }
+
+    #[test]
+    fn test_junit_rules_are_unique() {
+        let file = File::test("App\\Invoice", &[]);
+        let violation = |rule, subject: &str| Violation {
+            rule,
+            class: file.class.name.to_owned(),
+            path: file.path.to_owned(),
+            subject: Some(subject.to_string()),
+            value: 2.0,
+            limit: 1.0,
+            message: format!("{subject} is too complex"),
+            line: 1,
+            related_lines: Vec::new(),
+        };
+        let violations = [
+            violation(Rule::FunctionComplexity, "a"),
+            violation(Rule::Lines, "b"),
+            violation(Rule::FunctionComplexity, "c"),
+        ];
+        let xml = junit(&[file], &violations);
+        assert!(xml.contains("type=\"function-complexity,lines\""));
+        assert!(xml.contains("failures=\"1\""));
+    }
+}
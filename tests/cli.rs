@@ -0,0 +1,89 @@
+use std::{fs, path::PathBuf, process::Command};
+
+/// A scratch project with one class that breaks the complexity threshold.
+fn project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tcplscan-test-{}-{name}", std::process::id()));
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("tcplscan.toml"),
+        "[thresholds]\nfunction_complexity = 1\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/Invoice.php"),
+        "<?php\nnamespace App;\n\nclass Invoice\n{\n    public function total($a)\n    {\n        if ($a) { return 1; }\n        return 0;\n    }\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn run(dir: &PathBuf, args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tcplscan"))
+        .arg(dir)
+        .arg("--no-cache")
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_check_writes_only_json_to_stdout() {
+    let dir = project("check");
+    for format in ["json", "sarif"] {
+        let (code, stdout) = run(&dir, &["--check", "--format", format]);
+        assert_eq!(Some(1), code);
+        let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert!(json.is_array() || json.is_object());
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_report_writes_only_csv_to_stdout() {
+    let dir = project("report");
+    let (code, stdout) = run(&dir, &["--report", "--format", "csv"]);
+    assert_eq!(Some(0), code);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(2, lines.len());
+    assert!(lines[0].starts_with("class,path,"));
+    assert!(lines[1].starts_with("App\\Invoice,"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_changed_since_baseline_covers_the_whole_tree() {
+    let dir = project("baseline");
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "Invoice"]);
+    fs::write(
+        dir.join("src/Cart.php"),
+        "<?php\nnamespace App;\n\nclass Cart\n{\n}\n",
+    )
+    .unwrap();
+
+    let (code, _) = run(&dir, &["--changed-since", "HEAD", "--update-baseline"]);
+    assert_eq!(Some(0), code);
+    let (code, _) = run(&dir, &["--changed-since", "HEAD", "--prune-baseline"]);
+    assert_eq!(Some(0), code);
+    let baseline = fs::read_to_string(dir.join("tcplscan-baseline.json")).unwrap();
+    assert!(baseline.contains("App\\\\Invoice"));
+    let (code, _) = run(&dir, &["--check"]);
+    assert_eq!(Some(0), code);
+    fs::remove_dir_all(dir).unwrap();
+}